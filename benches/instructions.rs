@@ -0,0 +1,62 @@
+//! Instructions per second for the CPU on its own, over a flat bus.
+//!
+//! `cargo bench --bench instructions`
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::hint::black_box;
+use wasmboi::spec::bus::{Bus, FlatBus};
+use wasmboi::spec::cpu::{CPU, TCPU};
+use wasmboi::spec::register::TRegister;
+
+/// Instructions run per benchmark iteration.
+const INSTRUCTIONS: u64 = 10_000;
+
+/// A loop over loads, ALU ops, CB ops, the stack and memory.
+#[rustfmt::skip]
+const PROGRAM: &[u8] = &[
+    0x21, 0x00, 0xC0, // LD HL, 0xC000
+    0x3E, 0x12,       // LD A, 0x12
+    0x80,             // ADD A, B
+    0x0C,             // INC C
+    0x77,             // LD (HL), A
+    0x7E,             // LD A, (HL)
+    0xA8,             // XOR B
+    0xCB, 0x7C,       // BIT 7, H
+    0xCB, 0x11,       // RL C
+    0xC5,             // PUSH BC
+    0xD1,             // POP DE
+    0x23,             // INC HL
+    0xFE, 0x40,       // CP 0x40
+    0x18, 0xEB,       // JR -21
+];
+
+fn load() -> (CPU, FlatBus) {
+    let mut cpu = CPU::new().unwrap();
+    let mut bus = FlatBus::default();
+
+    for (offset, &byte) in PROGRAM.iter().enumerate() {
+        bus.write_byte(0x0100 + offset as u16, byte);
+    }
+    cpu.registers_mut().pc.set_value(0x0100);
+    cpu.registers_mut().sp.set_value(0xDFFE);
+
+    (cpu, bus)
+}
+
+fn instructions(c: &mut Criterion) {
+    let mut group = c.benchmark_group("instructions");
+    let (mut cpu, mut bus) = load();
+
+    group.throughput(Throughput::Elements(INSTRUCTIONS));
+    group.bench_function("mixed", |b| {
+        b.iter(|| {
+            for _ in 0..INSTRUCTIONS {
+                black_box(cpu.tick(&mut bus).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, instructions);
+criterion_main!(benches);
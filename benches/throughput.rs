@@ -0,0 +1,124 @@
+//! Emulation speed of the whole system, in MHz of emulated clock per second of
+//! wall time. Real hardware runs at 4.194304MHz.
+//!
+//! Each case runs with and without the block cache. `cargo bench --bench throughput
+//! [-- NAME...]` runs only the cases whose names contain one of the given strings.
+
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use wasmboi::spec::gameboy::{GameBoy, StopReason};
+use wasmboi::test_support::rom_with_program;
+
+const FIXTURES: &str = "./tests/fixtures";
+const ITERATIONS: usize = 3;
+/// About 5 seconds of emulated time.
+const T_CYCLES: u64 = 5 * 4_194_304;
+const CLOCK_MHZ: f64 = 4.194304;
+
+/// Adds up registers in a tight loop.
+#[rustfmt::skip]
+const ALU_LOOP: &[u8] = &[
+    0x80, // ADD A, B
+    0x89, // ADC A, C
+    0x92, // SUB D
+    0xA3, // AND E
+    0xAC, // XOR H
+    0xB5, // OR L
+    0xB8, // CP B
+    0x3C, // INC A
+    0x05, // DEC B
+    0x27, // DAA
+    0x2F, // CPL
+    0x18, 0xF4, // JR -12
+];
+
+/// Sleeps until every VBlank, with nothing to do in the handler.
+#[rustfmt::skip]
+const HALT_LOOP: &[u8] = &[
+    0x3E, 0x01, // LD A, 0x01
+    0xE0, 0xFF, // LDH (IE), A
+    0xFB,       // EI
+    0x76,       // HALT
+    0x18, 0xFD, // JR -3
+];
+
+/// `program` at the entry point, with a VBlank handler that just returns.
+fn rom_with_vblank_handler(program: &[u8]) -> Vec<u8> {
+    let mut rom = rom_with_program(program);
+    rom[0x40] = 0xD9; // RETI
+
+    rom
+}
+
+fn cases() -> Vec<(String, Vec<u8>)> {
+    let mut cases = vec![
+        ("alu_loop".to_owned(), rom_with_vblank_handler(ALU_LOOP)),
+        ("halt_loop".to_owned(), rom_with_vblank_handler(HALT_LOOP)),
+    ];
+
+    let mut fixtures: Vec<_> = fs::read_dir(FIXTURES)
+        .expect("Failed to read fixtures")
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "gb"))
+        .collect();
+    fixtures.sort();
+
+    for path in fixtures {
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        cases.push((name, fs::read(&path).unwrap()));
+    }
+
+    cases
+}
+
+fn run(rom: &[u8], block_cache: bool) -> Duration {
+    let mut gameboy = GameBoy::new(rom).expect("Failed to load ROM");
+    gameboy.set_block_cache(block_cache);
+
+    let start = Instant::now();
+    let result = gameboy.run_cycles(T_CYCLES);
+    let elapsed = start.elapsed();
+
+    if let StopReason::Error(e) = result.stop {
+        panic!("Emulation failed with {:?}", e);
+    }
+
+    elapsed
+}
+
+fn main() {
+    let filters: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+
+    assert!(Path::new(FIXTURES).exists(), "Run from the crate root");
+
+    for (name, rom) in cases() {
+        for block_cache in [false, true] {
+            let name = match block_cache {
+                true => format!("{}+cache", name),
+                false => name.clone(),
+            };
+
+            if !filters.is_empty() && !filters.iter().any(|filter| name.contains(filter)) {
+                continue;
+            }
+
+            let best = (0..ITERATIONS)
+                .map(|_| run(&rom, block_cache))
+                .min()
+                .unwrap();
+            let mhz = T_CYCLES as f64 / best.as_secs_f64() / 1e6;
+
+            println!(
+                "{:<28} {:>8.1?} {:>8.1}MHz {:>6.1}x",
+                name,
+                best,
+                mhz,
+                mhz / CLOCK_MHZ
+            );
+        }
+    }
+}
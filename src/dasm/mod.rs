@@ -1,4 +1,4 @@
-use crate::spec::opcode::{instruction_lookup, Instruction};
+use crate::spec::opcode::{instruction_lookup, Instruction, CB_PREFIX};
 
 use crate::util::byte_ops::{extract_lhs, extract_rhs};
 
@@ -38,13 +38,128 @@ impl From<u8> for ByteData {
     }
 }
 
+/// Operands encoded in the opcode itself, picked out once when the decode table
+/// is built. Which of them an instruction uses depends on the instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Operands {
+    /// Bits 3-5, the register written to.
+    pub r: u8,
+    /// Bits 0-2, the register read from.
+    pub r_prime: u8,
+    /// Bits 4-5, the register pair, as `dd` or `qq`.
+    pub pair: u8,
+    /// Bits 3-4, the condition a branch is taken on.
+    pub condition: u8,
+    /// Bits 3-5 as the mask of the bit tested, set or reset.
+    pub bit: u8,
+    /// Bits 3-5 as the address RST calls.
+    pub vector: u16,
+}
+
+impl From<u8> for Operands {
+    fn from(byte: u8) -> Self {
+        let r = extract_lhs(byte);
+
+        Operands {
+            r,
+            r_prime: extract_rhs(byte),
+            pair: r >> 1,
+            condition: r & 0b11,
+            bit: 1 << r,
+            vector: r as u16 * 8,
+        }
+    }
+}
+
+/// What sort of instruction it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Group {
+    Ld,
+    Stack,
+    Alu,
+    Bitwise,
+    Control,
+    Branch,
+    Unimplemented,
+}
+
+impl From<&Mnemonic> for Group {
+    fn from(mnemonic: &Mnemonic) -> Self {
+        match mnemonic {
+            Mnemonic::LD | Mnemonic::LDHL => Group::Ld,
+            Mnemonic::PUSH | Mnemonic::POP => Group::Stack,
+            Mnemonic::ADD
+            | Mnemonic::ADC
+            | Mnemonic::SUB
+            | Mnemonic::SBC
+            | Mnemonic::AND
+            | Mnemonic::XOR
+            | Mnemonic::OR
+            | Mnemonic::CP
+            | Mnemonic::INC
+            | Mnemonic::DEC
+            | Mnemonic::DAA
+            | Mnemonic::CPL => Group::Alu,
+            Mnemonic::RLCA
+            | Mnemonic::RLA
+            | Mnemonic::RRCA
+            | Mnemonic::RRA
+            | Mnemonic::RLC
+            | Mnemonic::RL
+            | Mnemonic::RRC
+            | Mnemonic::RR
+            | Mnemonic::SLA
+            | Mnemonic::SWAP
+            | Mnemonic::SRA
+            | Mnemonic::SET
+            | Mnemonic::BIT
+            | Mnemonic::RES
+            | Mnemonic::SRL => Group::Bitwise,
+            Mnemonic::CCF
+            | Mnemonic::SCF
+            | Mnemonic::NOP
+            | Mnemonic::HALT
+            | Mnemonic::STOP
+            | Mnemonic::DI
+            | Mnemonic::EI => Group::Control,
+            Mnemonic::JP
+            | Mnemonic::JR
+            | Mnemonic::CALL
+            | Mnemonic::RET
+            | Mnemonic::RETI
+            | Mnemonic::RST
+            | Mnemonic::DB
+            | Mnemonic::DW => Group::Branch,
+            Mnemonic::UNIMPLEMENTED => Group::Unimplemented,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct InstructionData {
     pub byte: u8,
     pub size: usize,
     pub instruction: Instruction,
     pub mnemonic: Mnemonic,
-    pub opcode_info: ByteData,
+    pub group: Group,
+    pub operands: Operands,
+}
+
+lazy_static! {
+    /// Every unprefixed opcode, followed by every CB prefixed one.
+    static ref DECODE_TABLE: Vec<InstructionData> = (0..=0xFF)
+        .map(|byte| (byte, None))
+        .chain((0..=0xFF).map(|cb_byte| (CB_PREFIX, Some(cb_byte))))
+        .map(|bytes| InstructionData::try_from(bytes).unwrap())
+        .collect();
+}
+
+/// Looks up an already decoded instruction.
+pub fn decode(byte: u8, cb_byte: Option<u8>) -> &'static InstructionData {
+    match cb_byte {
+        Some(cb_byte) => &DECODE_TABLE[0x100 + cb_byte as usize],
+        None => &DECODE_TABLE[byte as usize],
+    }
 }
 
 impl TryFrom<(u8, Option<u8>)> for InstructionData {
@@ -57,16 +172,15 @@ impl TryFrom<(u8, Option<u8>)> for InstructionData {
         };
         let mnemonic = Mnemonic::from(&instruction);
         let size = Instruction::get_size(&instruction);
-        let byte_data = maybe_cb_byte
-            .map(ByteData::from)
-            .unwrap_or(ByteData::from(byte));
+        let operands = Operands::from(maybe_cb_byte.unwrap_or(byte));
 
         Ok(InstructionData {
             byte: maybe_cb_byte.unwrap_or(byte),
             instruction,
             size,
+            group: Group::from(&mnemonic),
             mnemonic,
-            opcode_info: byte_data,
+            operands,
         })
     }
 }
@@ -76,7 +190,7 @@ impl fmt::Display for InstructionData {
         write!(
             f,
             "{:#X}: {:?} (size: {}) {:?}",
-            self.byte, self.instruction, self.size, self.opcode_info
+            self.byte, self.instruction, self.size, self.operands
         )
     }
 }
@@ -237,3 +351,32 @@ impl fmt::Display for InstructionData {
 //
 //     Ok(disassembly)
 // }
+
+#[cfg(test)]
+mod dasm_test {
+    use crate::dasm::decode;
+    use crate::spec::opcode::{Instruction, CB_PREFIX};
+
+    #[test]
+    fn operands_are_decoded_with_the_instruction() {
+        let rst = decode(0xFF, None);
+        assert!(matches!(rst.instruction, Instruction::RST));
+        assert_eq!(rst.operands.vector, 0x38);
+
+        let jr = decode(0x38, None);
+        assert!(matches!(jr.instruction, Instruction::JR_FPCDD));
+        assert_eq!(jr.operands.condition, 0b11);
+
+        let push = decode(0xD5, None);
+        assert!(matches!(push.instruction, Instruction::PUSH_RR));
+        assert_eq!(push.operands.pair, 0b01);
+
+        let ld = decode(0x78, None);
+        assert!(matches!(ld.instruction, Instruction::LD_RR));
+        assert_eq!((ld.operands.r, ld.operands.r_prime), (0b111, 0b000));
+
+        let bit = decode(CB_PREFIX, Some(0x7C));
+        assert!(matches!(bit.instruction, Instruction::BIT_NR));
+        assert_eq!((bit.operands.bit, bit.operands.r_prime), (0x80, 0b100));
+    }
+}
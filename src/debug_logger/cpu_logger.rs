@@ -33,6 +33,12 @@ impl FromEnvList for CpuDebug {
     }
 }
 
+impl CpuDebug {
+    pub fn gb_doc(&self) -> bool {
+        self.gb_doc
+    }
+}
+
 impl DebugLogger for CpuDebug {
     #[cfg(debug_assertions)]
     fn log<F>(&self, t: &str, f: F)
@@ -0,0 +1,406 @@
+use crate::link::null::NullCable;
+use std::ops::RangeInclusive;
+
+pub mod serial_output;
+
+/// What happened during the step a device is being ticked for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tick {
+    /// T-cycles elapsed since power on, at the end of the step.
+    pub now: u64,
+    /// T-cycles since the last tick. A step that accessed IO partway through is
+    /// ticked in parts.
+    pub t_cycles: u64,
+    /// The byte shifted out of the serial port, if a transfer completed.
+    pub serial_sent: Option<u8>,
+}
+
+/// Something plugged into the Game Boy.
+///
+/// A device can claim address ranges on the memory bus, which take priority over
+/// whatever the Game Boy would otherwise map there, and is ticked after every
+/// step. The one connected as the link cable is also the other end of the serial
+/// port. Everything has a default, so a device only implements what it uses.
+pub trait Device: Send {
+    /// The addresses this device answers reads and writes on.
+    fn address_ranges(&self) -> Vec<RangeInclusive<u16>> {
+        vec![]
+    }
+
+    fn read(&self, _address: u16) -> u8 {
+        crate::spec::mmu::OPEN_BUS
+    }
+
+    fn write(&mut self, _address: u16, _value: u8) {}
+
+    /// Returns the interrupt bits to request.
+    fn tick(&mut self, _tick: &Tick) -> u8 {
+        0
+    }
+
+    /// The t-cycle by which this device next needs ticking, given the current
+    /// time, or `None` if it only reacts to what the Game Boy does. Devices are
+    /// still ticked after every step, but a halted CPU skips ahead no further than
+    /// this. By default that's `now`, so the device sees every step.
+    fn next_tick(&self, now: u64) -> Option<u64> {
+        Some(now)
+    }
+
+    /// Link cable only. Sends the byte shifted out of SB once the side using the
+    /// internal clock has shifted all 8 bits, and returns the byte shifted in. With
+    /// nothing driving the other end of the line, this reads as 0xFF.
+    fn exchange(&mut self, _outgoing: u8) -> u8 {
+        0xFF
+    }
+
+    /// Link cable only. Called while SB waits on an external clock, with the byte
+    /// that will be shifted out. Returns the byte shifted in once the other end has
+    /// clocked a transfer.
+    fn poll_external(&mut self, _outgoing: u8) -> Option<u8> {
+        None
+    }
+
+    /// Everything needed to restore this device with `load_state`.
+    fn save_state(&self) -> Vec<u8> {
+        vec![]
+    }
+
+    fn load_state(&mut self, _state: &[u8]) {}
+}
+
+/// The devices attached to a Game Boy, along with the address ranges they claim
+/// and which of them is the link cable.
+#[derive(Default)]
+pub struct DeviceBus {
+    devices: Vec<Box<dyn Device>>,
+    ranges: Vec<(RangeInclusive<u16>, usize)>,
+    link: Option<usize>,
+}
+
+impl DeviceBus {
+    pub fn attach(&mut self, device: Box<dyn Device>) {
+        self.devices.push(device);
+        self.index_ranges();
+    }
+
+    /// Plugs `device` into the serial port, unplugging whatever was there before.
+    pub fn connect_link(&mut self, device: Box<dyn Device>) {
+        match self.link {
+            Some(index) => {
+                self.devices[index] = device;
+                self.index_ranges();
+            }
+            None => {
+                self.link = Some(self.devices.len());
+                self.attach(device);
+            }
+        }
+    }
+
+    fn index_ranges(&mut self) {
+        self.ranges = self
+            .devices
+            .iter()
+            .enumerate()
+            .flat_map(|(index, device)| {
+                device
+                    .address_ranges()
+                    .into_iter()
+                    .map(move |range| (range, index))
+            })
+            .collect();
+    }
+
+    /// The device claiming `address`, if any. The first device attached wins.
+    fn owner(&self, address: u16) -> Option<usize> {
+        self.ranges
+            .iter()
+            .find(|(range, _)| range.contains(&address))
+            .map(|(_, index)| *index)
+    }
+
+    pub fn claims(&self, address: u16) -> bool {
+        !self.ranges.is_empty() && self.owner(address).is_some()
+    }
+
+    pub fn read(&self, address: u16) -> Option<u8> {
+        if self.ranges.is_empty() {
+            return None;
+        }
+
+        self.owner(address)
+            .map(|index| self.devices[index].read(address))
+    }
+
+    /// Returns whether a device took the write.
+    pub fn write(&mut self, address: u16, value: u8) -> bool {
+        if self.ranges.is_empty() {
+            return false;
+        }
+
+        match self.owner(address) {
+            Some(index) => {
+                self.devices[index].write(address, value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Swaps bytes with the link cable, behaving like a `NullCable` with none connected.
+    pub fn exchange(&mut self, outgoing: u8) -> u8 {
+        match self.link {
+            Some(index) => self.devices[index].exchange(outgoing),
+            None => NullCable.exchange(outgoing),
+        }
+    }
+
+    /// Polls the link cable for a transfer clocked by the other end.
+    pub fn poll_external(&mut self, outgoing: u8) -> Option<u8> {
+        match self.link {
+            Some(index) => self.devices[index].poll_external(outgoing),
+            None => NullCable.poll_external(outgoing),
+        }
+    }
+
+    /// Ticks every device, returning the interrupt bits they request.
+    pub fn tick(&mut self, tick: &Tick) -> u8 {
+        self.devices
+            .iter_mut()
+            .fold(0, |interrupts, device| interrupts | device.tick(tick))
+    }
+
+    /// The earliest t-cycle any device next needs ticking by.
+    pub fn next_tick(&self, now: u64) -> Option<u64> {
+        self.devices
+            .iter()
+            .filter_map(|device| device.next_tick(now))
+            .min()
+    }
+
+    /// The state of each device, in the order they were attached.
+    pub fn save_state(&self) -> Vec<Vec<u8>> {
+        self.devices
+            .iter()
+            .map(|device| device.save_state())
+            .collect()
+    }
+
+    pub fn load_state(&mut self, states: &[Vec<u8>]) {
+        for (device, state) in self.devices.iter_mut().zip(states) {
+            device.load_state(state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod device_test {
+    use crate::device::serial_output::SerialOutput;
+    use crate::device::{Device, DeviceBus, Tick};
+    use crate::link::capture::ByteCapture;
+    use crate::link::loopback::LoopbackCable;
+    use crate::link::null::NullCable;
+    use crate::spec::gameboy::GameBoy;
+    use crate::spec::hardware_registers::Interrupt;
+    use crate::test_support::rom_with_program;
+    use std::ops::RangeInclusive;
+    use std::sync::{Arc, Mutex};
+
+    /// A byte of RAM on the otherwise unusable region that requests the timer
+    /// interrupt once a given t-cycle has passed.
+    #[derive(Default)]
+    struct Latch {
+        value: u8,
+        alarm: u64,
+    }
+
+    impl Device for Latch {
+        fn address_ranges(&self) -> Vec<RangeInclusive<u16>> {
+            vec![0xFEA0..=0xFEA0]
+        }
+
+        fn read(&self, _address: u16) -> u8 {
+            self.value
+        }
+
+        fn write(&mut self, _address: u16, value: u8) {
+            self.value = value;
+        }
+
+        fn tick(&mut self, tick: &Tick) -> u8 {
+            if self.alarm > 0 && tick.now >= self.alarm {
+                self.alarm = 0;
+                Interrupt::Timer.get_position()
+            } else {
+                0
+            }
+        }
+
+        fn next_tick(&self, _now: u64) -> Option<u64> {
+            (self.alarm > 0).then_some(self.alarm)
+        }
+
+        fn save_state(&self) -> Vec<u8> {
+            vec![self.value]
+        }
+
+        fn load_state(&mut self, state: &[u8]) {
+            self.value = state[0];
+        }
+    }
+
+    #[test]
+    fn devices_answer_on_their_address_ranges() {
+        let rom = rom_with_program(&[
+            0x3E, 0x42, // LD A, 0x42
+            0xEA, 0xA0, 0xFE, // LD (0xFEA0), A
+            0x00, // NOP
+        ]);
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        gameboy.attach_device(Box::new(Latch::default()));
+        gameboy.cycle().unwrap();
+        gameboy.cycle().unwrap();
+
+        assert_eq!(gameboy.mmu.read_byte(0xFEA0), 0x42);
+        assert_eq!(gameboy.mmu.read_byte(0xFEA1), 0xFF);
+        assert_eq!(gameboy.device_states(), vec![vec![0x42]]);
+
+        gameboy.load_device_states(&[vec![0x17]]);
+        assert_eq!(gameboy.mmu.read_byte(0xFEA0), 0x17);
+    }
+
+    #[test]
+    fn devices_raise_interrupts_when_ticked() {
+        let rom = rom_with_program(&[0x18, 0xFE]); // JR -2
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        gameboy.attach_device(Box::new(Latch {
+            value: 0,
+            alarm: 100,
+        }));
+
+        while gameboy.t_cycles() < 100 {
+            assert_eq!(gameboy.mmu.read_byte(0xFF0F) & 0b100, 0);
+            gameboy.cycle().unwrap();
+        }
+
+        assert_eq!(gameboy.mmu.read_byte(0xFF0F) & 0b100, 0b100);
+    }
+
+    #[test]
+    fn serial_output_receives_sent_bytes() {
+        let rom = rom_with_program(&[
+            0x3E, 0x21, // LD A, 0x21
+            0xE0, 0x01, // LDH (SB), A
+            0x3E, 0x81, // LD A, 0x81
+            0xE0, 0x02, // LDH (SC), A
+            0x18, 0xFE, // JR -2
+        ]);
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+        let received = Arc::new(Mutex::new(String::new()));
+        let output = received.clone();
+
+        gameboy.attach_device(Box::new(SerialOutput::new(move |c| {
+            output.lock().unwrap().push(c)
+        })));
+
+        while gameboy.t_cycles() < 5000 {
+            gameboy.cycle().unwrap();
+        }
+
+        assert_eq!(*received.lock().unwrap(), "!");
+    }
+
+    #[test]
+    fn unconnected_link_shifts_in_ones() {
+        let mut devices = DeviceBus::default();
+
+        devices.attach(Box::new(Latch::default()));
+
+        assert_eq!(devices.exchange(0x42), 0xFF);
+        assert_eq!(devices.poll_external(0x42), None);
+    }
+
+    #[test]
+    fn connecting_a_link_replaces_the_previous_one() {
+        let mut devices = DeviceBus::default();
+        let capture = ByteCapture::default();
+
+        devices.connect_link(Box::new(capture.clone()));
+        devices.attach(Box::new(Latch::default()));
+        assert_eq!(devices.exchange(0x42), 0xFF);
+
+        devices.connect_link(Box::new(LoopbackCable));
+        assert_eq!(devices.exchange(0x17), 0x17);
+
+        assert_eq!(capture.bytes(), vec![0x42]);
+        assert_eq!(devices.save_state().len(), 2);
+        assert!(devices.claims(0xFEA0));
+    }
+
+    #[test]
+    fn link_cable_swaps_bytes_on_transfers() {
+        let rom = rom_with_program(&[
+            0x3E, 0x21, // LD A, 0x21
+            0xE0, 0x01, // LDH (SB), A
+            0x3E, 0x81, // LD A, 0x81
+            0xE0, 0x02, // LDH (SC), A
+            0x18, 0xFE, // JR -2
+        ]);
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        gameboy.connect_link_cable(Box::new(LoopbackCable));
+
+        while gameboy.t_cycles() < 5000 {
+            gameboy.cycle().unwrap();
+        }
+
+        assert_eq!(gameboy.mmu.read_byte(0xFF01), 0x21);
+        assert_eq!(gameboy.mmu.read_byte(0xFF0F) & 0b1000, 0b1000);
+    }
+
+    /// Runs a transfer of 0x21 on the clock selected by `sc`, then returns SB, SC
+    /// and whether the serial interrupt was requested.
+    fn transfer_without_a_cable(sc: u8, unplug: bool) -> (u8, u8, bool) {
+        let rom = rom_with_program(&[
+            0x3E, 0x21, // LD A, 0x21
+            0xE0, 0x01, // LDH (SB), A
+            0x3E, sc, // LD A, sc
+            0xE0, 0x02, // LDH (SC), A
+            0x18, 0xFE, // JR -2
+        ]);
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        if unplug {
+            gameboy.connect_link_cable(Box::new(LoopbackCable));
+            gameboy.connect_link_cable(Box::new(NullCable));
+        }
+
+        while gameboy.t_cycles() < 10_000 {
+            gameboy.cycle().unwrap();
+        }
+
+        (
+            gameboy.mmu.read_byte(0xFF01),
+            gameboy.mmu.read_byte(0xFF02),
+            gameboy.mmu.read_byte(0xFF0F) & 0b1000 != 0,
+        )
+    }
+
+    #[test]
+    fn transfers_without_a_cable_shift_in_ones() {
+        for unplug in [false, true] {
+            assert_eq!(transfer_without_a_cable(0x81, unplug), (0xFF, 0x7F, true));
+            // Nothing ever clocks an external transfer
+            assert_eq!(transfer_without_a_cable(0x80, unplug), (0x21, 0xFE, false));
+        }
+    }
+
+    #[test]
+    fn gameboy_can_be_sent_across_threads() {
+        fn assert_send<T: Send>() {}
+
+        assert_send::<GameBoy>();
+    }
+}
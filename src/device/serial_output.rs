@@ -0,0 +1,29 @@
+use crate::device::{Device, Tick};
+
+/// Hands every byte sent over the serial port to a callback, as a character.
+/// Test ROMs commonly report their results this way.
+pub struct SerialOutput {
+    output: Box<dyn FnMut(char) + Send>,
+}
+
+impl SerialOutput {
+    pub fn new<F: FnMut(char) + Send + 'static>(output: F) -> Self {
+        SerialOutput {
+            output: Box::new(output),
+        }
+    }
+}
+
+impl Device for SerialOutput {
+    fn tick(&mut self, tick: &Tick) -> u8 {
+        if let Some(byte) = tick.serial_sent {
+            (self.output)(byte as char);
+        }
+
+        0
+    }
+
+    fn next_tick(&self, _now: u64) -> Option<u64> {
+        None
+    }
+}
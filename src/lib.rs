@@ -5,6 +5,13 @@ extern crate core;
 
 pub mod dasm;
 pub mod debug_logger;
+pub mod device;
+pub mod link;
 pub mod mbc;
+pub mod movie;
+pub mod pacing;
 pub mod spec;
 pub mod util;
+
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
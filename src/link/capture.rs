@@ -0,0 +1,43 @@
+use crate::device::Device;
+use std::sync::{Arc, Mutex};
+
+/// Records every byte sent over the serial port, like a terminal on the other
+/// end of the line. Test ROMs print their results this way.
+///
+/// Clones share the same buffer, so keep one to read from after handing the
+/// other to the Game Boy.
+#[derive(Clone, Default)]
+pub struct ByteCapture {
+    bytes: Arc<Mutex<Vec<u8>>>,
+}
+
+impl ByteCapture {
+    pub fn len(&self) -> usize {
+        self.bytes.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn bytes(&self) -> Vec<u8> {
+        self.bytes.lock().unwrap().clone()
+    }
+
+    /// The captured bytes as text.
+    pub fn output(&self) -> String {
+        self.bytes().iter().map(|&byte| byte as char).collect()
+    }
+}
+
+impl Device for ByteCapture {
+    fn exchange(&mut self, outgoing: u8) -> u8 {
+        self.bytes.lock().unwrap().push(outgoing);
+
+        0xFF
+    }
+
+    fn next_tick(&self, _now: u64) -> Option<u64> {
+        None
+    }
+}
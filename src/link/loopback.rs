@@ -0,0 +1,16 @@
+use crate::device::Device;
+
+/// A cable with its ends plugged into each other, so every byte sent is received
+/// straight back.
+#[derive(Default)]
+pub struct LoopbackCable;
+
+impl Device for LoopbackCable {
+    fn exchange(&mut self, outgoing: u8) -> u8 {
+        outgoing
+    }
+
+    fn next_tick(&self, _now: u64) -> Option<u64> {
+        None
+    }
+}
@@ -0,0 +1,6 @@
+pub mod capture;
+pub mod loopback;
+pub mod null;
+pub mod pair;
+pub mod printer;
+pub mod tcp;
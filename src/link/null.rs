@@ -0,0 +1,21 @@
+use crate::device::Device;
+
+/// No cable plugged in. Transfers on the internal clock shift in 0xFF, and
+/// transfers on an external clock never complete. Connecting one unplugs
+/// whatever cable was there before.
+#[derive(Default)]
+pub struct NullCable;
+
+impl Device for NullCable {
+    fn exchange(&mut self, _outgoing: u8) -> u8 {
+        0xFF
+    }
+
+    fn poll_external(&mut self, _outgoing: u8) -> Option<u8> {
+        None
+    }
+
+    fn next_tick(&self, _now: u64) -> Option<u64> {
+        None
+    }
+}
@@ -0,0 +1,179 @@
+use crate::device::Device;
+use crate::spec::gameboy::{GameBoy, GameBoyError};
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct Line {
+    /// The byte in SB of each end that is waiting on an external clock.
+    waiting: [Option<u8>; 2],
+    /// The byte clocked in by the other end, for each end that was waiting.
+    received: [Option<u8>; 2],
+}
+
+/// One end of a cable between two emulated Game Boys.
+pub struct PairedCable {
+    end: usize,
+    line: Arc<Mutex<Line>>,
+}
+
+impl PairedCable {
+    /// Both ends of a new cable.
+    pub fn pair() -> (PairedCable, PairedCable) {
+        let line = Arc::new(Mutex::new(Line::default()));
+
+        (
+            PairedCable {
+                end: 0,
+                line: line.clone(),
+            },
+            PairedCable { end: 1, line },
+        )
+    }
+
+    fn other_end(&self) -> usize {
+        1 - self.end
+    }
+}
+
+impl Device for PairedCable {
+    fn exchange(&mut self, outgoing: u8) -> u8 {
+        let other = self.other_end();
+        let mut line = self.line.lock().unwrap();
+
+        match line.waiting[other].take() {
+            Some(incoming) => {
+                line.received[other] = Some(outgoing);
+                incoming
+            }
+            None => 0xFF,
+        }
+    }
+
+    fn poll_external(&mut self, outgoing: u8) -> Option<u8> {
+        let mut line = self.line.lock().unwrap();
+
+        match line.received[self.end].take() {
+            Some(incoming) => Some(incoming),
+            None => {
+                line.waiting[self.end] = Some(outgoing);
+                None
+            }
+        }
+    }
+}
+
+/// Two Game Boys connected by a link cable, run in lockstep.
+///
+/// Whichever machine is behind is stepped next, so the two are never more than
+/// one instruction, or one halted stretch, apart.
+pub struct LinkedPair {
+    first: GameBoy,
+    second: GameBoy,
+}
+
+impl LinkedPair {
+    pub fn new(mut first: GameBoy, mut second: GameBoy) -> Self {
+        let (first_end, second_end) = PairedCable::pair();
+
+        first.connect_link_cable(Box::new(first_end));
+        second.connect_link_cable(Box::new(second_end));
+
+        LinkedPair { first, second }
+    }
+
+    /// Steps whichever machine is behind by one cycle.
+    pub fn step(&mut self) -> Result<(), GameBoyError> {
+        if self.first.t_cycles() <= self.second.t_cycles() {
+            self.first.cycle()?;
+        } else {
+            self.second.cycle()?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs both machines until each has executed at least `t_cycles` more
+    /// t-cycles.
+    pub fn run_for(&mut self, t_cycles: u64) -> Result<(), GameBoyError> {
+        let until = self.first.t_cycles().max(self.second.t_cycles()) + t_cycles;
+
+        while self.first.t_cycles() < until || self.second.t_cycles() < until {
+            self.step()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn first(&self) -> &GameBoy {
+        &self.first
+    }
+
+    pub fn first_mut(&mut self) -> &mut GameBoy {
+        &mut self.first
+    }
+
+    pub fn second(&self) -> &GameBoy {
+        &self.second
+    }
+
+    pub fn second_mut(&mut self) -> &mut GameBoy {
+        &mut self.second
+    }
+
+    pub fn into_inner(self) -> (GameBoy, GameBoy) {
+        (self.first, self.second)
+    }
+}
+
+#[cfg(test)]
+mod pair_test {
+    use crate::link::pair::LinkedPair;
+    use crate::spec::gameboy::GameBoy;
+    use crate::spec::hardware_registers::io_address::{SB, SC};
+    use crate::test_support::rom_with_program;
+
+    /// Loads `sb` into SB, starts a transfer with the given SC and waits for it
+    /// to finish.
+    fn transfer_program(sb: u8, sc: u8) -> Vec<u8> {
+        rom_with_program(&[
+            0x3E, sb, // LD A, sb
+            0xE0, 0x01, // LDH (SB), A
+            0x3E, sc, // LD A, sc
+            0xE0, 0x02, // LDH (SC), A
+            0xF0, 0x02, // LDH A, (SC)
+            0xCB, 0x7F, // BIT 7, A
+            0x20, 0xFA, // JR NZ, -6
+            0x18, 0xFE, // JR -2
+        ])
+    }
+
+    #[test]
+    fn master_and_slave_swap_bytes() {
+        let master_rom = transfer_program(0x42, 0x81);
+        let slave_rom = transfer_program(0x99, 0x80);
+        let mut pair = LinkedPair::new(
+            GameBoy::new(&master_rom).unwrap(),
+            GameBoy::new(&slave_rom).unwrap(),
+        );
+
+        pair.run_for(8192).unwrap();
+
+        let (master, slave) = pair.into_inner();
+
+        assert_eq!(master.mmu.read_byte(SB), 0x99);
+        assert_eq!(slave.mmu.read_byte(SB), 0x42);
+        assert_eq!(master.mmu.read_byte(SC) & 0x80, 0);
+        assert_eq!(slave.mmu.read_byte(SC) & 0x80, 0);
+    }
+
+    #[test]
+    fn machines_stay_in_step() {
+        let rom = transfer_program(0, 0);
+        let mut pair = LinkedPair::new(GameBoy::new(&rom).unwrap(), GameBoy::new(&rom).unwrap());
+
+        pair.run_for(100_000).unwrap();
+
+        let drift = pair.first().t_cycles().abs_diff(pair.second().t_cycles());
+        assert!(drift <= 24, "machines drifted {} t-cycles apart", drift);
+    }
+}
@@ -0,0 +1,421 @@
+use crate::device::Device;
+use crate::util::image::{save_png, Palette};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const MAGIC: [u8; 2] = [0x88, 0x33];
+const DEVICE_ID: u8 = 0x81;
+
+const INITIALIZE: u8 = 0x01;
+const PRINT: u8 = 0x02;
+const DATA: u8 = 0x04;
+const STATUS: u8 = 0x0F;
+
+const STATUS_CHECKSUM_ERROR: u8 = 0b1;
+const STATUS_PRINTING: u8 = 0b10;
+const STATUS_UNPROCESSED_DATA: u8 = 0b1000;
+
+/// How many status packets report the printer as busy after a print command.
+const PRINTING_STATUS_POLLS: u8 = 4;
+
+const WIDTH: usize = 160;
+const TILES_PER_ROW: usize = WIDTH / 8;
+const TILE_SIZE: usize = 16;
+
+/// The palette used when a print command leaves it as zero.
+const DEFAULT_PALETTE: u8 = 0xE4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Receiving {
+    Magic(usize),
+    Command,
+    Compression,
+    LengthLow,
+    LengthHigh,
+    Data,
+    ChecksumLow,
+    ChecksumHigh,
+    DeviceId,
+    Status,
+}
+
+/// A printed strip of paper. Each pixel is a shade from 0 (white) to 3 (black).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrintedImage {
+    pub width: usize,
+    pub height: usize,
+    pub shades: Vec<u8>,
+}
+
+impl PrintedImage {
+    /// The image as 8-bit RGB pixels, on white paper.
+    pub fn to_rgb(&self) -> Vec<u8> {
+        Palette::GRAYSCALE.to_rgb(&self.shades)
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        save_png(path, self.width, self.height, &self.to_rgb())
+    }
+}
+
+struct Printer {
+    receiving: Receiving,
+    command: u8,
+    compressed: bool,
+    length: usize,
+    data: Vec<u8>,
+    checksum: u16,
+    received_checksum: u16,
+    status: u8,
+    printing_polls: u8,
+    buffer: Vec<u8>,
+    printed: Vec<PrintedImage>,
+    output_dir: Option<PathBuf>,
+    saved: usize,
+    save_errors: Vec<(PathBuf, io::Error)>,
+}
+
+impl Default for Printer {
+    fn default() -> Self {
+        Printer {
+            receiving: Receiving::Magic(0),
+            command: 0,
+            compressed: false,
+            length: 0,
+            data: vec![],
+            checksum: 0,
+            received_checksum: 0,
+            status: 0,
+            printing_polls: 0,
+            buffer: vec![],
+            printed: vec![],
+            output_dir: None,
+            saved: 0,
+            save_errors: vec![],
+        }
+    }
+}
+
+impl Printer {
+    /// Takes the next byte of a packet, returning the byte shifted back to the
+    /// Game Boy.
+    fn receive(&mut self, byte: u8) -> u8 {
+        let mut reply = 0;
+
+        self.receiving = match self.receiving {
+            Receiving::Magic(index) if byte == MAGIC[index] => {
+                if index + 1 == MAGIC.len() {
+                    Receiving::Command
+                } else {
+                    Receiving::Magic(index + 1)
+                }
+            }
+            Receiving::Magic(_) => Receiving::Magic(0),
+            Receiving::Command => {
+                self.command = byte;
+                self.checksum = byte as u16;
+                self.data.clear();
+                Receiving::Compression
+            }
+            Receiving::Compression => {
+                self.compressed = byte & 1 == 1;
+                self.checksum = self.checksum.wrapping_add(byte as u16);
+                Receiving::LengthLow
+            }
+            Receiving::LengthLow => {
+                self.length = byte as usize;
+                self.checksum = self.checksum.wrapping_add(byte as u16);
+                Receiving::LengthHigh
+            }
+            Receiving::LengthHigh => {
+                self.length |= (byte as usize) << 8;
+                self.checksum = self.checksum.wrapping_add(byte as u16);
+
+                if self.length == 0 {
+                    Receiving::ChecksumLow
+                } else {
+                    Receiving::Data
+                }
+            }
+            Receiving::Data => {
+                self.data.push(byte);
+                self.checksum = self.checksum.wrapping_add(byte as u16);
+
+                if self.data.len() == self.length {
+                    Receiving::ChecksumLow
+                } else {
+                    Receiving::Data
+                }
+            }
+            Receiving::ChecksumLow => {
+                self.received_checksum = byte as u16;
+                Receiving::ChecksumHigh
+            }
+            Receiving::ChecksumHigh => {
+                self.received_checksum |= (byte as u16) << 8;
+                Receiving::DeviceId
+            }
+            Receiving::DeviceId => {
+                reply = DEVICE_ID;
+                self.handle_packet();
+                Receiving::Status
+            }
+            Receiving::Status => {
+                reply = self.status;
+                Receiving::Magic(0)
+            }
+        };
+
+        reply
+    }
+
+    fn handle_packet(&mut self) {
+        if self.checksum != self.received_checksum {
+            self.status |= STATUS_CHECKSUM_ERROR;
+            return;
+        }
+
+        self.status &= !STATUS_CHECKSUM_ERROR;
+
+        match self.command {
+            INITIALIZE => {
+                self.buffer.clear();
+                self.status = 0;
+                self.printing_polls = 0;
+            }
+            DATA => {
+                let data = std::mem::take(&mut self.data);
+
+                if self.compressed {
+                    self.buffer.extend(decompress(&data));
+                } else {
+                    self.buffer.extend(data);
+                }
+
+                if !self.buffer.is_empty() {
+                    self.status |= STATUS_UNPROCESSED_DATA;
+                }
+            }
+            PRINT => {
+                let palette = match self.data.get(2) {
+                    Some(0) | None => DEFAULT_PALETTE,
+                    Some(&palette) => palette,
+                };
+
+                self.print(palette);
+                self.status = (self.status & !STATUS_UNPROCESSED_DATA) | STATUS_PRINTING;
+                self.printing_polls = PRINTING_STATUS_POLLS;
+            }
+            STATUS if self.printing_polls > 0 => {
+                self.printing_polls -= 1;
+
+                if self.printing_polls == 0 {
+                    self.status &= !STATUS_PRINTING;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Decodes the buffered 2bpp tiles into an image, 20 tiles to a row.
+    fn print(&mut self, palette: u8) {
+        let rows = self.buffer.len() / (TILES_PER_ROW * TILE_SIZE);
+        let height = rows * 8;
+        let mut shades = vec![0; WIDTH * height];
+
+        for (tile_index, tile) in self.buffer.chunks_exact(TILE_SIZE).enumerate() {
+            let tile_x = (tile_index % TILES_PER_ROW) * 8;
+            let tile_y = (tile_index / TILES_PER_ROW) * 8;
+
+            if tile_y >= height {
+                break;
+            }
+
+            for (line, bytes) in tile.chunks_exact(2).enumerate() {
+                for pixel in 0..8 {
+                    let bit = 7 - pixel;
+                    let color = ((bytes[1] >> bit) & 1) << 1 | ((bytes[0] >> bit) & 1);
+                    let shade = (palette >> (color * 2)) & 0b11;
+
+                    shades[(tile_y + line) * WIDTH + tile_x + pixel] = shade;
+                }
+            }
+        }
+
+        self.buffer.clear();
+
+        let image = PrintedImage {
+            width: WIDTH,
+            height,
+            shades,
+        };
+
+        if let Some(dir) = &self.output_dir {
+            let path = dir.join(format!("print_{:03}.png", self.saved));
+
+            if let Err(e) = image.save_png(&path) {
+                self.save_errors.push((path, e));
+            }
+
+            self.saved += 1;
+        }
+
+        self.printed.push(image);
+    }
+}
+
+/// Expands run length encoded packet data. A control byte with bit 7 set repeats
+/// the next byte `(control & 0x7F) + 2` times, otherwise the next `control + 1`
+/// bytes are copied as they are.
+fn decompress(data: &[u8]) -> Vec<u8> {
+    let mut output = vec![];
+    let mut bytes = data.iter().copied();
+
+    while let Some(control) = bytes.next() {
+        if control & 0x80 != 0 {
+            if let Some(byte) = bytes.next() {
+                output.resize(output.len() + (control & 0x7F) as usize + 2, byte);
+            }
+        } else {
+            output.extend(bytes.by_ref().take(control as usize + 1));
+        }
+    }
+
+    output
+}
+
+/// The Game Boy Printer, plugged into the link port.
+///
+/// Clones share the same printer, so keep one to collect prints from after
+/// connecting the other to the Game Boy.
+#[derive(Clone, Default)]
+pub struct GameBoyPrinter {
+    printer: Arc<Mutex<Printer>>,
+}
+
+impl GameBoyPrinter {
+    /// A printer that also saves every print as a PNG file in `dir`.
+    pub fn saving_to<P: AsRef<Path>>(dir: P) -> Self {
+        let printer = GameBoyPrinter::default();
+        printer.printer.lock().unwrap().output_dir = Some(dir.as_ref().to_path_buf());
+
+        printer
+    }
+
+    /// Removes and returns everything printed so far.
+    pub fn take_images(&self) -> Vec<PrintedImage> {
+        std::mem::take(&mut self.printer.lock().unwrap().printed)
+    }
+
+    /// Removes and returns the prints that couldn't be saved, with where they
+    /// were going. They're still kept for `take_images`.
+    pub fn take_save_errors(&self) -> Vec<(PathBuf, io::Error)> {
+        std::mem::take(&mut self.printer.lock().unwrap().save_errors)
+    }
+}
+
+impl Device for GameBoyPrinter {
+    fn exchange(&mut self, outgoing: u8) -> u8 {
+        self.printer.lock().unwrap().receive(outgoing)
+    }
+
+    fn next_tick(&self, _now: u64) -> Option<u64> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod printer_test {
+    use crate::device::Device;
+    use crate::link::printer::{decompress, GameBoyPrinter};
+
+    /// Sends a packet and returns the status byte.
+    fn send_packet(printer: &mut GameBoyPrinter, command: u8, compressed: bool, data: &[u8]) -> u8 {
+        let mut body = vec![command, compressed as u8];
+        body.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        body.extend_from_slice(data);
+
+        let checksum = body
+            .iter()
+            .fold(0u16, |sum, &byte| sum.wrapping_add(byte as u16));
+
+        let mut packet = vec![0x88, 0x33];
+        packet.extend(body);
+        packet.extend_from_slice(&checksum.to_le_bytes());
+
+        for byte in packet {
+            assert_eq!(printer.exchange(byte), 0);
+        }
+
+        assert_eq!(printer.exchange(0), 0x81);
+        printer.exchange(0)
+    }
+
+    #[test]
+    fn prints_buffered_tiles() {
+        let mut printer = GameBoyPrinter::default();
+
+        // Two rows of tiles, the first tile using color 3 on every line
+        let mut tiles = vec![0; 0x280];
+        tiles[..16].fill(0xFF);
+
+        assert_eq!(send_packet(&mut printer, 0x01, false, &[]), 0);
+        assert_eq!(send_packet(&mut printer, 0x04, false, &tiles), 0b1000);
+        assert_eq!(send_packet(&mut printer, 0x04, false, &[]), 0b1000);
+        assert_eq!(
+            send_packet(&mut printer, 0x02, false, &[1, 0x13, 0xE4, 0x40]),
+            0b10
+        );
+
+        let images = printer.take_images();
+        assert_eq!(images.len(), 1);
+        assert_eq!((images[0].width, images[0].height), (160, 16));
+        assert_eq!(images[0].shades[0], 3);
+        assert_eq!(images[0].shades[8], 0);
+        assert_eq!(images[0].to_rgb()[..3], [0, 0, 0]);
+
+        for _ in 0..4 {
+            send_packet(&mut printer, 0x0F, false, &[]);
+        }
+
+        assert_eq!(send_packet(&mut printer, 0x0F, false, &[]), 0);
+    }
+
+    #[test]
+    fn bad_checksum_is_reported() {
+        let mut printer = GameBoyPrinter::default();
+
+        for byte in [0x88, 0x33, 0x0F, 0, 0, 0, 0xFF, 0xFF] {
+            printer.exchange(byte);
+        }
+
+        assert_eq!(printer.exchange(0), 0x81);
+        assert_eq!(printer.exchange(0), 0b1);
+    }
+
+    #[test]
+    fn failed_saves_are_kept_for_the_frontend() {
+        let dir = std::env::temp_dir()
+            .join(format!("wasmboi-printer-{}", std::process::id()))
+            .join("missing");
+        let mut printer = GameBoyPrinter::saving_to(&dir);
+
+        assert_eq!(send_packet(&mut printer, 0x04, false, &[0; 0x280]), 0b1000);
+        send_packet(&mut printer, 0x02, false, &[1, 0x13, 0xE4, 0x40]);
+
+        let errors = printer.take_save_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, dir.join("print_000.png"));
+        assert!(printer.take_save_errors().is_empty());
+        assert_eq!(printer.take_images().len(), 1);
+    }
+
+    #[test]
+    fn decompresses_runs_and_literals() {
+        assert_eq!(
+            decompress(&[0x81, 0xAA, 0x01, 0x12, 0x34]),
+            [0xAA, 0xAA, 0xAA, 0x12, 0x34]
+        );
+    }
+}
@@ -0,0 +1,339 @@
+use crate::device::{Device, Tick};
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+const READY: u8 = 0;
+const TRANSFER: u8 = 1;
+const CANCEL: u8 = 2;
+
+/// Every message is a tag, a byte and a little endian sequence number.
+const FRAME_SIZE: usize = 4;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Message {
+    /// The other end is waiting on an external clock with this byte in SB. Each
+    /// wait gets a new sequence number.
+    Ready(u8, u16),
+    /// The other end stopped waiting without a transfer.
+    Cancel(u16),
+    /// The other end clocked a transfer, shifting out this byte, against the wait
+    /// with this sequence number.
+    Transfer(u8, u16),
+}
+
+impl Message {
+    fn decode(frame: [u8; FRAME_SIZE]) -> Option<Message> {
+        let sequence = u16::from_le_bytes([frame[2], frame[3]]);
+
+        match frame[0] {
+            READY => Some(Message::Ready(frame[1], sequence)),
+            CANCEL => Some(Message::Cancel(sequence)),
+            TRANSFER => Some(Message::Transfer(frame[1], sequence)),
+            _ => None,
+        }
+    }
+
+    fn encode(&self) -> [u8; FRAME_SIZE] {
+        let (tag, byte, sequence) = match *self {
+            Message::Ready(byte, sequence) => (READY, byte, sequence),
+            Message::Cancel(sequence) => (CANCEL, 0, sequence),
+            Message::Transfer(byte, sequence) => (TRANSFER, byte, sequence),
+        };
+        let [low, high] = sequence.to_le_bytes();
+
+        [tag, byte, low, high]
+    }
+}
+
+/// A link cable to another emulator over TCP.
+///
+/// The clocking side never waits on the network. Each end announces when it's
+/// waiting on an external clock, and when it stops waiting without a transfer.
+/// The side with the internal clock completes the transfer against the last wait
+/// announced, or against 0xFF if the other end isn't waiting, as if nothing was
+/// connected. The waiting side completes its transfer whenever the clocked byte
+/// arrives, so latency only delays the slave.
+///
+/// Transfers carry the sequence number of the wait they answer, and the waiting
+/// side drops any that aren't for its current wait. A transfer clocked against
+/// a wait that has since been cancelled or replaced can't complete a later one.
+///
+/// If the connection drops the cable behaves as if it were unplugged.
+pub struct TcpCable {
+    stream: Option<TcpStream>,
+    incoming: Receiver<Message>,
+    /// The byte and sequence number of the other end's wait.
+    peer_waiting: Option<(u8, u16)>,
+    /// The byte announced for our own wait, numbered `sequence`.
+    announced: Option<u8>,
+    sequence: u16,
+    /// Whether the serial port polled for an external clock since the last tick.
+    polled: bool,
+}
+
+impl TcpCable {
+    /// Waits for another emulator to join on `address`.
+    pub fn host<A: ToSocketAddrs>(address: A) -> io::Result<TcpCable> {
+        let listener = TcpListener::bind(address)?;
+        let (stream, _) = listener.accept()?;
+
+        TcpCable::from_stream(stream)
+    }
+
+    /// Connects to an emulator hosting on `address`.
+    pub fn join<A: ToSocketAddrs>(address: A) -> io::Result<TcpCable> {
+        TcpCable::from_stream(TcpStream::connect(address)?)
+    }
+
+    pub fn from_stream(stream: TcpStream) -> io::Result<TcpCable> {
+        stream.set_nodelay(true)?;
+
+        let mut reader = stream.try_clone()?;
+        let (sender, incoming) = mpsc::channel();
+
+        thread::spawn(move || {
+            let mut frame = [0; FRAME_SIZE];
+
+            while reader.read_exact(&mut frame).is_ok() {
+                let message = match Message::decode(frame) {
+                    Some(message) => message,
+                    None => break,
+                };
+
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(TcpCable {
+            stream: Some(stream),
+            incoming,
+            peer_waiting: None,
+            announced: None,
+            sequence: 0,
+            polled: false,
+        })
+    }
+
+    fn send(&mut self, message: Message) {
+        if let Some(stream) = &mut self.stream {
+            if stream.write_all(&message.encode()).is_err() {
+                self.stream = None;
+            }
+        }
+    }
+
+    /// Handles everything received so far, returning a clocked byte if there was
+    /// one for our current wait.
+    fn receive(&mut self) -> Option<u8> {
+        loop {
+            match self.incoming.try_recv() {
+                Ok(Message::Ready(byte, sequence)) => self.peer_waiting = Some((byte, sequence)),
+                Ok(Message::Cancel(sequence)) => {
+                    if matches!(self.peer_waiting, Some((_, waiting)) if waiting == sequence) {
+                        self.peer_waiting = None;
+                    }
+                }
+                Ok(Message::Transfer(byte, sequence)) => {
+                    if self.announced.is_some() && sequence == self.sequence {
+                        self.announced = None;
+                        return Some(byte);
+                    }
+                }
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    self.stream = None;
+                    self.peer_waiting = None;
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+impl Device for TcpCable {
+    fn tick(&mut self, _tick: &Tick) -> u8 {
+        // The serial port stopped waiting on an external clock without a transfer
+        if !std::mem::take(&mut self.polled) && self.announced.take().is_some() {
+            self.send(Message::Cancel(self.sequence));
+        }
+
+        0
+    }
+
+    fn exchange(&mut self, outgoing: u8) -> u8 {
+        self.receive();
+
+        match self.peer_waiting.take() {
+            Some((incoming, sequence)) => {
+                self.send(Message::Transfer(outgoing, sequence));
+                incoming
+            }
+            None => 0xFF,
+        }
+    }
+
+    fn poll_external(&mut self, outgoing: u8) -> Option<u8> {
+        self.polled = true;
+
+        if self.announced != Some(outgoing) {
+            self.announced = Some(outgoing);
+            self.sequence = self.sequence.wrapping_add(1);
+            self.send(Message::Ready(outgoing, self.sequence));
+        }
+
+        self.receive()
+    }
+}
+
+#[cfg(test)]
+mod tcp_test {
+    use crate::device::{Device, Tick};
+    use crate::link::tcp::{Message, TcpCable, FRAME_SIZE};
+    use crate::spec::gameboy::GameBoy;
+    use crate::spec::hardware_registers::io_address::SB;
+    use crate::test_support::rom_with_program;
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// Sends 0x42 on the internal clock until something other than 0xFF comes back.
+    const MASTER: [u8; 21] = [
+        0x3E, 0x42, // LD A, 0x42
+        0xE0, 0x01, // LDH (SB), A
+        0x3E, 0x81, // LD A, 0x81
+        0xE0, 0x02, // LDH (SC), A
+        0xF0, 0x02, // LDH A, (SC)
+        0xCB, 0x7F, // BIT 7, A
+        0x20, 0xFA, // JR NZ, -6
+        0xF0, 0x01, // LDH A, (SB)
+        0x3C, // INC A
+        0x28, 0xED, // JR Z, -19
+        0x18, 0xFE, // JR -2
+    ];
+
+    /// Waits on the external clock with 0x99 in SB.
+    const SLAVE: [u8; 16] = [
+        0x3E, 0x99, // LD A, 0x99
+        0xE0, 0x01, // LDH (SB), A
+        0x3E, 0x80, // LD A, 0x80
+        0xE0, 0x02, // LDH (SC), A
+        0xF0, 0x02, // LDH A, (SC)
+        0xCB, 0x7F, // BIT 7, A
+        0x20, 0xFA, // JR NZ, -6
+        0x18, 0xFE, // JR -2
+    ];
+
+    fn run_until_sb(cable: TcpCable, program: Vec<u8>, expected: u8) -> u8 {
+        let rom = rom_with_program(&program);
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+        let started = Instant::now();
+
+        gameboy.connect_link_cable(Box::new(cable));
+
+        while started.elapsed() < Duration::from_secs(5) {
+            gameboy.cycle().unwrap();
+
+            if gameboy.mmu.read_byte(SB) == expected {
+                break;
+            }
+        }
+
+        gameboy.mmu.read_byte(SB)
+    }
+
+    /// A cable, and the raw socket on its other end.
+    fn cable_and_peer() -> (TcpCable, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let peer = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+
+        (TcpCable::from_stream(stream).unwrap(), peer)
+    }
+
+    fn send(peer: &mut TcpStream, message: Message) {
+        peer.write_all(&message.encode()).unwrap();
+    }
+
+    fn receive(peer: &mut TcpStream) -> Message {
+        let mut frame = [0; FRAME_SIZE];
+        peer.read_exact(&mut frame).unwrap();
+
+        Message::decode(frame).unwrap()
+    }
+
+    fn poll_until_clocked(cable: &mut TcpCable, outgoing: u8) -> u8 {
+        let started = Instant::now();
+
+        while started.elapsed() < Duration::from_secs(5) {
+            if let Some(incoming) = cable.poll_external(outgoing) {
+                return incoming;
+            }
+
+            cable.tick(&Tick::default());
+        }
+
+        panic!("Transfer never arrived");
+    }
+
+    #[test]
+    fn transfers_for_an_earlier_wait_are_dropped() {
+        let (mut cable, mut peer) = cable_and_peer();
+
+        assert_eq!(cable.poll_external(0x11), None);
+        assert_eq!(receive(&mut peer), Message::Ready(0x11, 1));
+
+        // A step without polling means the wait was given up
+        cable.tick(&Tick::default());
+        cable.tick(&Tick::default());
+        assert_eq!(receive(&mut peer), Message::Cancel(1));
+
+        send(&mut peer, Message::Transfer(0x55, 1));
+        send(&mut peer, Message::Transfer(0x66, 2));
+
+        assert_eq!(poll_until_clocked(&mut cable, 0x11), 0x66);
+        assert_eq!(receive(&mut peer), Message::Ready(0x11, 2));
+    }
+
+    #[test]
+    fn cancelled_waits_are_not_clocked() {
+        let (mut cable, mut peer) = cable_and_peer();
+
+        send(&mut peer, Message::Ready(0x22, 7));
+        send(&mut peer, Message::Cancel(7));
+        thread::sleep(Duration::from_millis(100));
+
+        assert_eq!(cable.exchange(0x33), 0xFF);
+
+        send(&mut peer, Message::Ready(0x44, 8));
+        thread::sleep(Duration::from_millis(100));
+
+        assert_eq!(cable.exchange(0x33), 0x44);
+        assert_eq!(receive(&mut peer), Message::Transfer(0x33, 8));
+    }
+
+    #[test]
+    fn emulators_exchange_bytes_over_localhost() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let host = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            run_until_sb(
+                TcpCable::from_stream(stream).unwrap(),
+                MASTER.to_vec(),
+                0x99,
+            )
+        });
+        let join = thread::spawn(move || {
+            run_until_sb(TcpCable::join(address).unwrap(), SLAVE.to_vec(), 0x42)
+        });
+
+        assert_eq!(host.join().unwrap(), 0x99);
+        assert_eq!(join.join().unwrap(), 0x42);
+    }
+}
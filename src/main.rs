@@ -1,9 +1,167 @@
 use std::env;
 use std::fs;
+use std::io::{self, BufRead};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
 
-use wasmboi::spec::gameboy::{GameBoy, Peripheral};
+use wasmboi::device::serial_output::SerialOutput;
+use wasmboi::link::printer::GameBoyPrinter;
+use wasmboi::link::tcp::TcpCable;
+use wasmboi::movie::{Movie, Player, EMULATOR};
+use wasmboi::pacing::Pacer;
+use wasmboi::spec::gameboy::{GameBoy, RunResult, StopReason};
+use wasmboi::spec::ppu::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use wasmboi::util::image::{save_image, Palette};
+
+#[derive(Default)]
+struct Options {
+    screenshot_at_frame: Option<u64>,
+    screenshot: Option<String>,
+    palette: Palette,
+}
+
+impl Options {
+    /// `--screenshot-at-frame N` runs headless as fast as possible up to frame N,
+    /// saves it to `--screenshot PATH` (PNG, or PPM for a .ppm path) and exits.
+    /// `--palette` is `grayscale`, `green` or four RRGGBB colors, lightest first.
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+        let mut options = Options::default();
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+
+            match arg.as_str() {
+                "--screenshot-at-frame" => {
+                    let frame = value()?;
+                    options.screenshot_at_frame = Some(
+                        frame
+                            .parse()
+                            .map_err(|_| format!("Invalid frame {}", frame))?,
+                    );
+                }
+                "--screenshot" => options.screenshot = Some(value()?),
+                "--palette" => options.palette = value()?.parse()?,
+                _ => return Err(format!("Unknown option {}", arg)),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+fn take_screenshot(gameboy: &mut GameBoy, frame: u64, options: &Options) {
+    while gameboy.frames() < frame {
+        if let StopReason::Error(e) = gameboy.run_frame().stop {
+            panic!("Gameboy failed: {:?}", e);
+        }
+    }
+
+    let path = options.screenshot.as_deref().unwrap_or("screenshot.png");
+    let rgb = options.palette.to_rgb(gameboy.framebuffer());
+
+    save_image(path, SCREEN_WIDTH, SCREEN_HEIGHT, &rgb)
+        .unwrap_or_else(|e| panic!("Failed to save screenshot to {}: {:?}", path, e));
+}
+
+/// `hash ROM --frames N` runs ROM headless for N frames and prints
+/// `GameBoy::state_hash`, for checking runs against recorded hashes. `--input
+/// MOVIE` plays a `Movie` file along the way, holding its last buttons once it
+/// runs out.
+fn hash<I: Iterator<Item = String>>(mut args: I) -> Result<(), String> {
+    let rom_location = args.next().ok_or("hash needs a ROM")?;
+    let mut frames = None;
+    let mut movie = Movie::default();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+
+        match arg.as_str() {
+            "--frames" => {
+                let count = value()?;
+                frames = Some(
+                    count
+                        .parse::<u64>()
+                        .map_err(|_| format!("Invalid frame count {}", count))?,
+                );
+            }
+            "--input" => {
+                let path = value()?;
+                movie = fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read {}: {:?}", path, e))?
+                    .parse()?;
+            }
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+
+    let frames = frames.ok_or("hash needs --frames")?;
+    let rom =
+        fs::read(&rom_location).map_err(|e| format!("Failed to read {}: {:?}", rom_location, e))?;
+    let mut gameboy =
+        GameBoy::new(&rom).map_err(|e| format!("Failed to initialize GameBoy: {:?}", e))?;
+
+    movie.check_rom(&rom)?;
+
+    if let Some(emulator) = movie.emulator().filter(|&emulator| emulator != EMULATOR) {
+        eprintln!("Movie was recorded on {}, this is {}", emulator, EMULATOR);
+    }
+
+    let mut player = Player::new(movie);
+
+    for frame in 0..frames {
+        let result = match player.run_frame(&mut gameboy) {
+            Some(result) => result,
+            None => gameboy.run_frame(),
+        };
+
+        if let StopReason::Error(e) = result.stop {
+            return Err(format!("Gameboy failed on frame {}: {:?}", frame, e));
+        }
+    }
+
+    println!("{:016x}", gameboy.state_hash());
+
+    Ok(())
+}
+
+/// `BREAKPOINTS` is a comma separated list of hex addresses, like `0150,C000`.
+fn parse_breakpoints(breakpoints: &str) -> Result<Vec<u16>, String> {
+    breakpoints
+        .split(',')
+        .map(|address| {
+            let address = address.trim();
+            let digits = address.trim_start_matches("0x");
+
+            u16::from_str_radix(digits, 16).map_err(|_| format!("Invalid breakpoint {}", address))
+        })
+        .collect()
+}
+
+/// Sends a message for every line read from stdin, so pressing Enter can toggle
+/// pause. Stops once stdin closes.
+fn watch_enter() -> Receiver<()> {
+    let (sender, presses) = mpsc::channel();
+
+    thread::spawn(move || {
+        for _ in io::stdin().lock().lines() {
+            if sender.send(()).is_err() {
+                break;
+            }
+        }
+    });
+
+    presses
+}
 
 fn main() {
+    let mut args = env::args().skip(1).peekable();
+
+    if args.peek().map(String::as_str) == Some("hash") {
+        return hash(args.skip(1)).unwrap_or_else(|e| panic!("{}", e));
+    }
+
+    let options = Options::parse(args).unwrap_or_else(|e| panic!("{}", e));
+
     let rom_location = env::var("ROM").unwrap();
     // println!("Loading ${}", rom_location);
     let rom = fs::read(rom_location).unwrap();
@@ -15,13 +173,81 @@ fn main() {
         )
     });
 
-    gameboy.attach_peripheral(Peripheral::SerialPort(Box::new(|c| {
-        if env::var("SERIAL_PORT_STDOUT").unwrap_or("false".into()) == "true" {
-            if let Some(x) = c {
-                print!("{}", x)
-            }
+    gameboy.set_strict_memory(env::var("STRICT_MEMORY").unwrap_or("false".into()) == "true");
+    gameboy.set_block_cache(env::var("BLOCK_CACHE").unwrap_or("false".into()) == "true");
+
+    let mut printer = None;
+
+    if let Ok(address) = env::var("LINK_HOST") {
+        let cable = TcpCable::host(&address)
+            .unwrap_or_else(|e| panic!("Failed to host link cable on {}: {:?}", address, e));
+        gameboy.connect_link_cable(Box::new(cable));
+    } else if let Ok(address) = env::var("LINK_JOIN") {
+        let cable = TcpCable::join(&address)
+            .unwrap_or_else(|e| panic!("Failed to join link cable on {}: {:?}", address, e));
+        gameboy.connect_link_cable(Box::new(cable));
+    } else if let Ok(dir) = env::var("PRINTER_DIR") {
+        let cable = GameBoyPrinter::saving_to(dir);
+        gameboy.connect_link_cable(Box::new(cable.clone()));
+        printer = Some(cable);
+    }
+
+    if let Ok(breakpoints) = env::var("BREAKPOINTS") {
+        for pc in parse_breakpoints(&breakpoints).unwrap_or_else(|e| panic!("{}", e)) {
+            gameboy.add_breakpoint(pc);
         }
-    })));
+    }
+
+    gameboy.set_software_breakpoints(
+        env::var("SOFTWARE_BREAKPOINTS").unwrap_or("false".into()) == "true",
+    );
+
+    if env::var("SERIAL_PORT_STDOUT").unwrap_or("false".into()) == "true" {
+        gameboy.attach_device(Box::new(SerialOutput::new(|c| print!("{}", c))));
+    }
+
+    if let Some(frame) = options.screenshot_at_frame {
+        return take_screenshot(&mut gameboy, frame, &options);
+    }
+
+    let mut pacer = Pacer::default();
+    pacer.set_turbo(env::var("TURBO").unwrap_or("false".into()) == "true");
+
+    if let Ok(speed) = env::var("SPEED") {
+        pacer.set_speed(
+            speed
+                .parse()
+                .unwrap_or_else(|e| panic!("Invalid SPEED {}: {:?}", speed, e)),
+        );
+    }
+
+    let enter_presses = watch_enter();
 
-    gameboy.start().expect("Gameboy failed")
+    loop {
+        if enter_presses.try_recv().is_ok() {
+            pacer.set_paused(!pacer.paused());
+            eprintln!("{}", if pacer.paused() { "Paused" } else { "Resumed" });
+        }
+
+        let result = pacer.run_frame(&mut gameboy);
+
+        for (path, e) in printer.iter().flat_map(GameBoyPrinter::take_save_errors) {
+            eprintln!("Failed to save print to {}: {:?}", path.display(), e);
+        }
+
+        match result {
+            Some(RunResult {
+                stop: StopReason::Error(e),
+                ..
+            }) => panic!("Gameboy failed: {:?}", e),
+            Some(RunResult {
+                stop: StopReason::Breakpoint(pc) | StopReason::SoftwareBreakpoint(pc),
+                ..
+            }) => {
+                eprintln!("Stopped at breakpoint {:04X}", pc);
+                return;
+            }
+            _ => {}
+        }
+    }
 }
@@ -1,98 +1,106 @@
 use crate::mbc::{Mbc, MbcError};
 use crate::spec::memory_region::MemoryRegion;
-use std::num::Wrapping;
+use std::hash::{Hash, Hasher};
 
-#[derive(Default)]
+const ROM_BANK_SIZE: usize = 0x4000;
+const RAM_BANK_SIZE: usize = 0x2000;
+
+#[derive(Default, Hash)]
 pub struct Mbc1 {
-    rom: Box<[u8]>,
     ram: Box<[u8]>,
 
     ram_enable: bool,
+    /// The lower 5 bits of the ROM bank at 0x4000-0x7FFF. Never 0.
     rom_bank: u8,
-    rom_bank_offset: u16,
+    /// The RAM bank, or the upper 2 bits of the ROM bank.
     ram_bank: u8,
-    ram_bank_offset: u16,
     bank_mode: bool,
+    /// Bank numbers past the end of ROM wrap, since the pins above its size go nowhere.
+    rom_bank_mask: usize,
 }
 
 impl Mbc1 {
-    pub fn new(data: &[u8]) -> Self {
+    pub fn new(rom_size: usize) -> Self {
         Self {
-            rom: Box::from(data),
-            ram: Box::from([0; 0x7FFF]),
+            ram: Box::from([0; 0x8000]),
             ram_enable: false,
-            rom_bank: 0,
-            rom_bank_offset: 0,
+            rom_bank: 1,
             ram_bank: 0,
-            ram_bank_offset: 0,
             bank_mode: false,
+            rom_bank_mask: (rom_size / ROM_BANK_SIZE).next_power_of_two() - 1,
+        }
+    }
+
+    /// In RAM banking mode the upper bits bank 0x0000-0x3FFF and RAM as well as
+    /// 0x4000-0x7FFF. Otherwise they only apply to 0x4000-0x7FFF.
+    fn ram_offset(&self) -> usize {
+        if self.bank_mode {
+            self.ram_bank as usize * RAM_BANK_SIZE
+        } else {
+            0
         }
     }
 }
 
-impl Mbc for Mbc1 {}
+impl Mbc for Mbc1 {
+    fn rom_offsets(&self) -> [usize; 2] {
+        let upper_bits = (self.ram_bank as usize) << 5;
+        let lower = if self.bank_mode { upper_bits } else { 0 };
+
+        [
+            (lower & self.rom_bank_mask) * ROM_BANK_SIZE,
+            ((upper_bits | self.rom_bank as usize) & self.rom_bank_mask) * ROM_BANK_SIZE,
+        ]
+    }
+
+    fn hash_state(&self, mut state: &mut dyn Hasher) {
+        self.hash(&mut state);
+    }
+}
 
 impl MemoryRegion for Mbc1 {
     type Error = MbcError;
     fn map_read(&self, address: u16) -> Result<u8, MbcError> {
         match address {
-            0..=0x3FFF => Ok(self.rom[address as usize]),
-            0x4000..=0x7FFF => {
-                // println!("\t\tReading from ({:X}){:X}", address, (self.rom_bank_offset));
-
-                Ok(self.rom[(self.rom_bank_offset + (address)) as usize])
-            }
-            0xA000..=0xBFFF => {
-                let address = if self.bank_mode {
-                    address + (self.ram_bank_offset)
-                } else {
-                    address
-                };
-
-                Ok(self.ram[(address) as usize])
-            }
-            _ => Err(MbcError::Read(address)),
+            0xA000..=0xBFFF if self.ram_enable => self
+                .ram
+                .get(self.ram_offset() + (address & 0x1FFF) as usize),
+            _ => None,
         }
+        .copied()
+        .ok_or(MbcError::Read(address))
     }
 
     fn map_write(&mut self, address: u16, data: u8) -> Result<(), MbcError> {
-        // println!("\t\t[{:X}+{:X}]<-{:X}", address, self.rom_bank_offset, data);
         match address {
             0..=0x1FFF => {
                 self.ram_enable = (data & 0xF) == 0xA;
                 Ok(())
             }
             0x2000..=0x3FFF => {
-                self.rom_bank = (self.rom_bank & 0x60) + ((data & 0x1f) + 1);
-                self.rom_bank_offset = (Wrapping(self.rom_bank as u16) * Wrapping(0x4000)).0;
+                // Bank 0 can't be selected here, it reads as bank 1
                 // NOTE: Pan docs call out a caveat for mbc1m banking read more here: https://gbdev.io/pandocs/MBC1.html#MBC1M_banking
-
+                self.rom_bank = (data & 0x1F).max(1);
                 Ok(())
             }
             0x4000..=0x5FFF => {
-                if self.bank_mode {
-                    // RAM Banking Mode
-                    self.ram_bank = data & 0b11;
-                    self.rom_bank_offset = (self.ram_bank as u16) & 0x2000;
-                } else {
-                    // Simple Banking Mode
-                    self.rom_bank = ((data & 0x3) << 5) + (self.rom_bank & 0x1f);
-                    println!("Setting bank offset with bank: {:X}", self.rom_bank);
-                    self.rom_bank_offset = (Wrapping(self.rom_bank as u16) * Wrapping(0x4000)).0;
-                }
-
+                self.ram_bank = data & 0b11;
                 Ok(())
             }
             0x6000..=0x7FFF => {
                 self.bank_mode = (data & 1) == 1;
                 Ok(())
             }
-            0xA000..=0xBFFF => {
-                if self.ram_enable {
-                    self.ram[(self.ram_bank_offset + (address & 0x1FFF)) as usize] = data;
-                }
+            0xA000..=0xBFFF if self.ram_enable => {
+                let offset = self.ram_offset() + (address & 0x1FFF) as usize;
 
-                Ok(())
+                match self.ram.get_mut(offset) {
+                    Some(byte) => {
+                        *byte = data;
+                        Ok(())
+                    }
+                    None => Err(MbcError::Write(address, data)),
+                }
             }
             _ => Err(MbcError::Write(address, data)),
         }
@@ -1,4 +1,5 @@
 use crate::spec::memory_region::MemoryRegion;
+use std::hash::Hasher;
 
 pub mod mbc1;
 pub mod rom;
@@ -9,4 +10,15 @@ pub enum MbcError {
     Write(u16, u8),
 }
 
-pub trait Mbc: MemoryRegion<Error = MbcError> {}
+/// The banking controller on a cartridge. The MMU holds the ROM itself, and the
+/// MBC handles RAM and tells the MMU which banks are mapped in.
+pub trait Mbc: MemoryRegion<Error = MbcError> + Send {
+    /// Where in ROM 0x0000-0x3FFF and 0x4000-0x7FFF start. This is read again after
+    /// every write to the MBC.
+    fn rom_offsets(&self) -> [usize; 2] {
+        [0, 0x4000]
+    }
+
+    /// Feeds the banking registers and RAM to `state`, for `GameBoy::state_hash`.
+    fn hash_state(&self, state: &mut dyn Hasher);
+}
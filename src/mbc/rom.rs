@@ -1,32 +1,36 @@
 use crate::mbc::{Mbc, MbcError};
 use crate::spec::memory_region::MemoryRegion;
+use std::hash::{Hash, Hasher};
 
-#[derive(Default)]
+#[derive(Default, Hash)]
 pub struct Rom {
-    rom: Box<[u8]>,
     ram: Box<[u8]>,
 }
 
 impl Rom {
-    pub fn new(data: &[u8]) -> Self {
+    pub fn new() -> Self {
         Self {
-            rom: Box::from(data),
-            ram: Box::from([0; 8191]),
+            ram: Box::from([0; 0x2000]),
         }
     }
 }
 
-impl Mbc for Rom {}
+impl Mbc for Rom {
+    fn hash_state(&self, mut state: &mut dyn Hasher) {
+        self.hash(&mut state);
+    }
+}
 
 impl MemoryRegion for Rom {
     type Error = MbcError;
 
     fn map_read(&self, address: u16) -> Result<u8, MbcError> {
         match address {
-            0x0000..=0x7FFF => Ok(self.rom[address as usize]),
-            0xA000..=0xBFFF => Ok(self.ram[(address - 0xA000) as usize]),
-            _ => Err(MbcError::Read(address)),
+            0xA000..=0xBFFF => self.ram.get((address - 0xA000) as usize),
+            _ => None,
         }
+        .copied()
+        .ok_or(MbcError::Read(address))
     }
 
     fn map_write(&mut self, address: u16, value: u8) -> Result<(), MbcError> {
@@ -0,0 +1,436 @@
+use crate::spec::gameboy::{GameBoy, RunResult, StopReason};
+use crate::spec::joypad::Button;
+use crate::util::hash::fnv1a;
+use std::fmt;
+use std::str::FromStr;
+
+/// The emulator movies are recorded with. Replaying a movie on another version
+/// can diverge wherever emulation changed in between.
+pub const EMULATOR: &str = concat!("wasmboi ", env!("CARGO_PKG_VERSION"));
+
+/// The letter for each button in a movie line, in `Button::ALL` order.
+const BUTTON_LETTERS: [char; 8] = ['R', 'L', 'U', 'D', 'A', 'B', 's', 'S'];
+
+/// Restarting the Game Boy at the start of a frame. Ordered so that a power
+/// cycle takes the place of a reset in the same frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Restart {
+    /// Restarts the console, leaving the cartridge as it is.
+    Reset,
+    /// Switches off and on again with the cartridge starting afresh, like a
+    /// new `GameBoy`.
+    Power,
+}
+
+/// The input for one frame: the buttons held during it, and whether it starts
+/// with a restart.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    pub restart: Option<Restart>,
+    /// One bit per button in `Button::ALL` order.
+    held: u8,
+}
+
+impl Frame {
+    pub fn is_held(&self, button: Button) -> bool {
+        self.held & Self::mask(button) != 0
+    }
+
+    pub fn set_held(&mut self, button: Button, held: bool) {
+        if held {
+            self.held |= Self::mask(button);
+        } else {
+            self.held &= !Self::mask(button);
+        }
+    }
+
+    fn mask(button: Button) -> u8 {
+        let bit = Button::ALL.iter().position(|&b| b == button).unwrap();
+
+        1 << bit
+    }
+}
+
+/// The input for a run, frame by frame, along with the ROM and emulator it was
+/// recorded on.
+///
+/// Movies are text, one line per frame. Each line has a column per button in
+/// `RLUDABsS` order (right, left, up, down, A, B, select, start), holding its
+/// letter when the button is held and `.` when it isn't, e.g. `..U.A...`. A
+/// frame that starts with a restart is prefixed with `reset` or `power`. Before
+/// the frames, `emulator NAME` and `rom HASH` lines record where the movie came
+/// from, `HASH` being the `fnv1a` of the ROM in hex. Both are optional, so
+/// input can be written by hand. Blank lines and lines starting with `#` are
+/// skipped.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Movie {
+    emulator: Option<String>,
+    rom: Option<u64>,
+    frames: Vec<Frame>,
+}
+
+impl Movie {
+    /// An empty movie to record `rom` on this emulator.
+    pub fn for_rom(rom: &[u8]) -> Movie {
+        Movie {
+            emulator: Some(EMULATOR.to_string()),
+            rom: Some(fnv1a(rom)),
+            frames: vec![],
+        }
+    }
+
+    /// The emulator the movie was recorded on, if it says.
+    pub fn emulator(&self) -> Option<&str> {
+        self.emulator.as_deref()
+    }
+
+    /// Fails if the movie was recorded on another ROM.
+    pub fn check_rom(&self, rom: &[u8]) -> Result<(), String> {
+        match self.rom {
+            Some(recorded) if recorded != fnv1a(rom) => Err(format!(
+                "Movie was recorded on ROM {:016x}, not {:016x}",
+                recorded,
+                fnv1a(rom)
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    fn parse_frame(line: &str) -> Result<Frame, String> {
+        let (restart, buttons) = match line.split_once(' ') {
+            Some(("reset", buttons)) => (Some(Restart::Reset), buttons.trim_start()),
+            Some(("power", buttons)) => (Some(Restart::Power), buttons.trim_start()),
+            Some((word, _)) => return Err(format!("Unknown restart {}", word)),
+            None => (None, line),
+        };
+        let columns: Vec<char> = buttons.chars().collect();
+
+        if columns.len() != BUTTON_LETTERS.len() {
+            return Err("expected 8 columns".to_string());
+        }
+
+        let held = columns
+            .iter()
+            .zip(BUTTON_LETTERS.iter())
+            .enumerate()
+            .try_fold(0, |held, (bit, (&column, &letter))| match column {
+                '.' => Ok(held),
+                _ if column == letter => Ok(held | (1 << bit)),
+                _ => Err(format!("expected {} or .", letter)),
+            })?;
+
+        Ok(Frame { restart, held })
+    }
+}
+
+impl FromStr for Movie {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut movie = Movie::default();
+
+        for (number, line) in s.lines().map(str::trim).enumerate() {
+            let error = |e: String| format!("Line {}: {}", number + 1, e);
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(emulator) = line.strip_prefix("emulator ") {
+                movie.emulator = Some(emulator.trim().to_string());
+            } else if let Some(rom) = line.strip_prefix("rom ") {
+                let hash = u64::from_str_radix(rom.trim(), 16)
+                    .map_err(|_| error(format!("Invalid ROM hash {}", rom.trim())))?;
+                movie.rom = Some(hash);
+            } else {
+                movie.frames.push(Self::parse_frame(line).map_err(error)?);
+            }
+        }
+
+        Ok(movie)
+    }
+}
+
+impl fmt::Display for Movie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(emulator) = &self.emulator {
+            writeln!(f, "emulator {}", emulator)?;
+        }
+
+        if let Some(rom) = self.rom {
+            writeln!(f, "rom {:016x}", rom)?;
+        }
+
+        for frame in &self.frames {
+            match frame.restart {
+                Some(Restart::Reset) => write!(f, "reset ")?,
+                Some(Restart::Power) => write!(f, "power ")?,
+                None => {}
+            }
+
+            for (&button, &letter) in Button::ALL.iter().zip(BUTTON_LETTERS.iter()) {
+                let column = if frame.is_held(button) { letter } else { '.' };
+                write!(f, "{}", column)?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Collects input while `GameBoy` records, see `GameBoy::start_recording`.
+pub(crate) struct Recorder {
+    movie: Movie,
+    next: Frame,
+}
+
+impl Recorder {
+    /// Starts with a power cycle, so the movie plays back from power on.
+    pub(crate) fn new(rom: &[u8], held: Frame) -> Recorder {
+        Recorder {
+            movie: Movie::for_rom(rom),
+            next: Frame {
+                restart: Some(Restart::Power),
+                ..held
+            },
+        }
+    }
+
+    pub(crate) fn set_button(&mut self, button: Button, pressed: bool) {
+        self.next.set_held(button, pressed);
+    }
+
+    pub(crate) fn restart(&mut self, restart: Restart) {
+        self.next.restart = self.next.restart.max(Some(restart));
+    }
+
+    /// Records the input for the frame about to run. Buttons stay held into the
+    /// next one.
+    pub(crate) fn next_frame(&mut self) -> Frame {
+        let frame = self.next;
+
+        self.movie.frames.push(frame);
+        self.next.restart = None;
+
+        frame
+    }
+
+    pub(crate) fn finish(self) -> Movie {
+        self.movie
+    }
+}
+
+/// Plays a movie back into a `GameBoy` from power on, giving exactly the run
+/// that was recorded. Anything plugged in, like a link cable, has to behave the
+/// same as it did then too.
+pub struct Player {
+    movie: Movie,
+    frame: usize,
+}
+
+impl Player {
+    pub fn new(movie: Movie) -> Player {
+        Player { movie, frame: 0 }
+    }
+
+    /// Frames played so far.
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn finished(&self) -> bool {
+        self.frame >= self.movie.len()
+    }
+
+    /// Applies the next frame's input and runs it, or returns None once the
+    /// movie is over.
+    pub fn run_frame(&mut self, gameboy: &mut GameBoy) -> Option<RunResult> {
+        let frame = *self.movie.frames.get(self.frame)?;
+
+        self.frame += 1;
+
+        Some(match gameboy.apply_input(frame) {
+            Ok(()) => gameboy.run_frame(),
+            Err(e) => RunResult {
+                t_cycles: 0,
+                frames: 0,
+                stop: StopReason::Error(e),
+            },
+        })
+    }
+}
+
+impl From<Player> for Movie {
+    fn from(player: Player) -> Movie {
+        player.movie
+    }
+}
+
+#[cfg(test)]
+mod movie_test {
+    use crate::movie::{Movie, Player, Restart};
+    use crate::spec::gameboy::{GameBoy, GameBoyError, StopReason};
+    use crate::spec::joypad::Button;
+    use crate::test_support::rom_with_program;
+
+    /// Logs P1 to 0xC000-0xC0FF over and over, with both button groups selected.
+    fn joypad_logger() -> Vec<u8> {
+        rom_with_program(&[
+            0x21, 0x00, 0xC0, // LD HL, 0xC000
+            0xAF, // XOR A
+            0xE0, 0x00, // LDH (P1), A
+            0xF0, 0x00, // LDH A, (P1)
+            0x77, // LD (HL), A
+            0x2C, // INC L
+            0x18, 0xF7, // JR -9
+        ])
+    }
+
+    #[test]
+    fn parses_a_line_per_frame() {
+        let movie: Movie = "emulator wasmboi 0.1.0\nrom 00000000000000ff\n\
+                            # Walk right, then jump\nR.......\n\nreset R...A...\npower ........\n"
+            .parse()
+            .unwrap();
+
+        assert_eq!(movie.emulator(), Some("wasmboi 0.1.0"));
+        assert_eq!(movie.rom, Some(0xFF));
+        assert_eq!(movie.len(), 3);
+        assert!(movie.frames()[1].is_held(Button::A));
+        assert!(movie.frames()[1].is_held(Button::Right));
+        assert!(!movie.frames()[1].is_held(Button::Left));
+        assert_eq!(movie.frames()[1].restart, Some(Restart::Reset));
+        assert_eq!(movie.frames()[2].restart, Some(Restart::Power));
+        assert_eq!(movie.to_string().parse::<Movie>(), Ok(movie));
+    }
+
+    #[test]
+    fn rejects_letters_in_the_wrong_column() {
+        assert_eq!(
+            "L.......".parse::<Movie>(),
+            Err("Line 1: expected R or .".to_string())
+        );
+        assert_eq!(
+            "R...A".parse::<Movie>(),
+            Err("Line 1: expected 8 columns".to_string())
+        );
+        assert_eq!(
+            "pause ........".parse::<Movie>(),
+            Err("Line 1: Unknown restart pause".to_string())
+        );
+    }
+
+    #[test]
+    fn checks_the_rom() {
+        let rom = joypad_logger();
+        let movie = Movie::for_rom(&rom);
+
+        assert!(movie.check_rom(&rom).is_ok());
+        assert!(movie.check_rom(&rom[1..]).is_err());
+        assert!(Movie::default().check_rom(&rom).is_ok());
+    }
+
+    #[test]
+    fn only_frames_run_while_recording() {
+        let rom = joypad_logger();
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        gameboy.start_recording();
+
+        let refused = gameboy.run_cycles(1000);
+        assert!(matches!(
+            refused.stop,
+            StopReason::Error(GameBoyError::Recording)
+        ));
+        assert_eq!(refused.t_cycles, 0);
+        assert!(matches!(
+            gameboy.run_until(|_| true).stop,
+            StopReason::Error(GameBoyError::Recording)
+        ));
+        assert!(matches!(gameboy.start(), Err(GameBoyError::Recording)));
+        assert!(matches!(gameboy.cycle(), Err(GameBoyError::Recording)));
+        assert_eq!(gameboy.t_cycles(), 0);
+        assert!(matches!(gameboy.run_frame().stop, StopReason::Frame));
+
+        gameboy.stop_recording();
+        assert!(matches!(
+            gameboy.run_cycles(1000).stop,
+            StopReason::CyclesElapsed
+        ));
+    }
+
+    #[test]
+    fn replays_a_recording_exactly() {
+        let rom = joypad_logger();
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+        let mut recorded = vec![];
+
+        gameboy.run_frame();
+        gameboy.start_recording();
+
+        for frame in 0..40 {
+            match frame {
+                3 => gameboy.set_button(Button::A, true).unwrap(),
+                5 => gameboy.set_button(Button::Up, true).unwrap(),
+                8 => gameboy.set_button(Button::A, false).unwrap(),
+                12 => gameboy.reset().unwrap(),
+                // Pressed and released between frames, so it never happens
+                15 => {
+                    gameboy.set_button(Button::Start, true).unwrap();
+                    gameboy.set_button(Button::Start, false).unwrap();
+                }
+                20 => gameboy.power_cycle().unwrap(),
+                25 => gameboy.set_button(Button::Up, false).unwrap(),
+                _ => {}
+            }
+
+            gameboy.run_frame();
+            recorded.push(gameboy.state_hash());
+        }
+
+        let movie: Movie = gameboy
+            .stop_recording()
+            .unwrap()
+            .to_string()
+            .parse()
+            .unwrap();
+        let mut replay = GameBoy::new(&rom).unwrap();
+        let mut player = Player::new(movie.clone());
+        let mut replayed = vec![];
+
+        while player.run_frame(&mut replay).is_some() {
+            replayed.push(replay.state_hash());
+        }
+
+        assert_eq!(recorded, replayed);
+        assert!(movie.frames()[4].is_held(Button::A));
+        assert_eq!(movie.frames()[12].restart, Some(Restart::Reset));
+        assert!(!movie.frames()[15].is_held(Button::Start));
+
+        // And the input made a difference
+        let mut idle = GameBoy::new(&rom).unwrap();
+        let idle_hashes: Vec<_> = (0..40)
+            .map(|_| {
+                idle.run_frame();
+                idle.state_hash()
+            })
+            .collect();
+
+        assert_eq!(recorded[..3], idle_hashes[..3]);
+        assert_ne!(recorded[3], idle_hashes[3]);
+    }
+}
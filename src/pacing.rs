@@ -0,0 +1,184 @@
+use crate::spec::gameboy::{GameBoy, RunResult};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How far emulation may fall behind the wall clock before the pacer gives up on
+/// catching up and starts pacing from wherever it is now.
+const MAX_LAG: Duration = Duration::from_millis(100);
+
+/// How long to wait between checks while paused.
+const PAUSED_POLL: Duration = Duration::from_millis(16);
+
+/// Keeps emulation in step with the wall clock, a frame at a time.
+///
+/// Emulated time is measured in t-cycles at the current speed mode's oscillation,
+/// so a double speed switch doesn't change how fast the game appears to run.
+pub struct Pacer {
+    speed: f64,
+    turbo: bool,
+    paused: bool,
+    anchor: Instant,
+    emulated: Duration,
+}
+
+impl Default for Pacer {
+    fn default() -> Self {
+        Pacer {
+            speed: 1.0,
+            turbo: false,
+            paused: false,
+            anchor: Instant::now(),
+            emulated: Duration::ZERO,
+        }
+    }
+}
+
+impl Pacer {
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Runs at `speed` times real time. 2.0 is twice as fast, 0.5 half as fast.
+    pub fn set_speed(&mut self, speed: f64) {
+        assert!(speed > 0.0, "Speed must be positive, got {}", speed);
+
+        self.speed = speed;
+        self.reset(Instant::now());
+    }
+
+    pub fn turbo(&self) -> bool {
+        self.turbo
+    }
+
+    /// While on, runs as fast as possible.
+    pub fn set_turbo(&mut self, turbo: bool) {
+        self.turbo = turbo;
+        self.reset(Instant::now());
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.reset(Instant::now());
+    }
+
+    /// Runs a frame and sleeps until it's due on the wall clock. While paused this
+    /// only waits briefly, so a frontend can keep polling input, and returns None.
+    pub fn run_frame(&mut self, gameboy: &mut GameBoy) -> Option<RunResult> {
+        if self.paused {
+            thread::sleep(PAUSED_POLL);
+            return None;
+        }
+
+        let oscillation = gameboy.speed_mode().oscillation();
+        let result = gameboy.run_frame();
+        let wait = self.wait_after(result.t_cycles, oscillation, Instant::now());
+
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+
+        Some(result)
+    }
+
+    /// Accounts for `t_cycles` of emulation, returning how long to wait from `now`
+    /// for the wall clock to catch up.
+    fn wait_after(&mut self, t_cycles: u64, oscillation: usize, now: Instant) -> Duration {
+        if self.turbo {
+            self.reset(now);
+            return Duration::ZERO;
+        }
+
+        self.emulated += Duration::from_secs_f64(t_cycles as f64 / oscillation as f64 / self.speed);
+
+        let due = self.anchor + self.emulated;
+
+        match due.checked_duration_since(now) {
+            Some(wait) => wait,
+            None => {
+                if now.duration_since(due) > MAX_LAG {
+                    self.reset(now);
+                }
+
+                Duration::ZERO
+            }
+        }
+    }
+
+    fn reset(&mut self, now: Instant) {
+        self.anchor = now;
+        self.emulated = Duration::ZERO;
+    }
+}
+
+#[cfg(test)]
+mod pacing_test {
+    use crate::pacing::Pacer;
+    use std::time::{Duration, Instant};
+
+    const FRAME: u64 = 70224;
+    const CLOCK: usize = 4194304;
+
+    fn millis(duration: Duration) -> f64 {
+        duration.as_secs_f64() * 1000.0
+    }
+
+    #[test]
+    fn frames_are_paced_at_the_clock_rate() {
+        let mut pacer = Pacer::default();
+        let now = Instant::now();
+        pacer.reset(now);
+
+        let wait = pacer.wait_after(FRAME, CLOCK, now);
+        assert!((millis(wait) - 16.74).abs() < 0.01);
+
+        // A second frame is due a frame later, less however long has passed
+        let wait = pacer.wait_after(FRAME, CLOCK, now + Duration::from_millis(20));
+        assert!((millis(wait) - 13.48).abs() < 0.01);
+    }
+
+    #[test]
+    fn speed_multipliers_scale_the_wait() {
+        let mut pacer = Pacer::default();
+        pacer.set_speed(2.0);
+        let now = Instant::now();
+        pacer.reset(now);
+
+        let wait = pacer.wait_after(FRAME, CLOCK, now);
+        assert!((millis(wait) - 8.37).abs() < 0.01);
+
+        // Double speed runs twice the t-cycles in the same time
+        let mut pacer = Pacer::default();
+        pacer.reset(now);
+
+        let wait = pacer.wait_after(FRAME * 2, CLOCK * 2, now);
+        assert!((millis(wait) - 16.74).abs() < 0.01);
+    }
+
+    #[test]
+    fn turbo_never_waits() {
+        let mut pacer = Pacer::default();
+        pacer.set_turbo(true);
+
+        assert_eq!(
+            pacer.wait_after(FRAME, CLOCK, Instant::now()),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn falling_far_behind_stops_catching_up() {
+        let mut pacer = Pacer::default();
+        let now = Instant::now();
+        pacer.reset(now);
+
+        let later = now + Duration::from_secs(1);
+        assert_eq!(pacer.wait_after(FRAME, CLOCK, later), Duration::ZERO);
+
+        let wait = pacer.wait_after(FRAME, CLOCK, later);
+        assert!((millis(wait) - 16.74).abs() < 0.01);
+    }
+}
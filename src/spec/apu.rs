@@ -0,0 +1,75 @@
+use crate::spec::hardware_registers::io_address::{NR10, NR52, WAVE_RAM_END, WAVE_RAM_START};
+use crate::spec::hardware_registers::IoHandler;
+use crate::spec::scheduler::Scheduler;
+
+const REGISTER_COUNT: usize = (WAVE_RAM_END - NR10) as usize + 1;
+const POWER: u8 = 0b1000_0000;
+
+/// The sound registers and wave RAM.
+///
+/// No audio is produced yet. The registers are stored so that games can read
+/// back what they wrote, and turning the APU off clears them like hardware does.
+/// The frame sequencer is stepped by the timer at 512Hz, ready for the length,
+/// sweep and envelope units to hang off it.
+#[derive(Hash)]
+pub struct Apu {
+    registers: [u8; REGISTER_COUNT],
+    /// The frame sequencer step to run next, 0-7.
+    frame_sequencer_step: u8,
+}
+
+impl Default for Apu {
+    fn default() -> Self {
+        let mut registers = [0; REGISTER_COUNT];
+        registers[(NR52 - NR10) as usize] = POWER;
+
+        Apu {
+            registers,
+            frame_sequencer_step: 0,
+        }
+    }
+}
+
+impl Apu {
+    /// Handles `Event::FrameSequencer`. It only runs while the APU is powered.
+    pub fn clock_frame_sequencer(&mut self) {
+        if self.powered() {
+            self.frame_sequencer_step = (self.frame_sequencer_step + 1) % 8;
+        }
+    }
+
+    pub fn frame_sequencer_step(&self) -> u8 {
+        self.frame_sequencer_step
+    }
+
+    fn powered(&self) -> bool {
+        self.registers[(NR52 - NR10) as usize] & POWER != 0
+    }
+}
+
+impl IoHandler for Apu {
+    fn read_register(&self, address: u16, _scheduler: &Scheduler) -> u8 {
+        self.registers[(address - NR10) as usize]
+    }
+
+    fn write_register(&mut self, address: u16, value: u8, _scheduler: &mut Scheduler) -> u8 {
+        match address {
+            NR52 => {
+                if value & POWER == 0 {
+                    self.registers[..(NR52 - NR10) as usize].fill(0);
+                } else if !self.powered() {
+                    // Powering on starts the frame sequencer again from step 0
+                    self.frame_sequencer_step = 0;
+                }
+
+                self.registers[(NR52 - NR10) as usize] = value & POWER;
+            }
+            WAVE_RAM_START..=WAVE_RAM_END => self.registers[(address - NR10) as usize] = value,
+            // Everything else is read only while the APU is off
+            _ if self.powered() => self.registers[(address - NR10) as usize] = value,
+            _ => {}
+        }
+
+        0
+    }
+}
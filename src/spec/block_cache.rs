@@ -0,0 +1,322 @@
+use crate::dasm::{decode, Group, InstructionData};
+use crate::spec::bus::{Access, AccessKind, Bus, CodeRegion};
+use crate::spec::cpu::{Error, CPU, TCPU};
+use crate::spec::opcode::{Instruction, CB_PREFIX};
+use crate::spec::register::TRegister;
+
+/// The most instructions decoded into one block.
+const MAX_BLOCK_LENGTH: usize = 64;
+const NO_BLOCK: u32 = u32::MAX;
+
+/// An instruction decoded ahead of time, along with the bytes `tick` would read
+/// for it: the opcode and the two after it.
+#[derive(Clone, Copy)]
+struct Decoded {
+    pc: u16,
+    bytes: [u8; 3],
+    instruction: &'static InstructionData,
+    /// `Bus::page_writes` when the bytes were last known to be current.
+    page_writes: u32,
+}
+
+impl Decoded {
+    fn matches<B: Bus>(&self, bus: &B) -> bool {
+        self.bytes
+            .iter()
+            .zip(0..)
+            .all(|(&byte, offset)| bus.read_byte(self.pc + offset) == byte)
+    }
+}
+
+/// Straight line code, up to and including the first instruction that can jump.
+struct Block {
+    region: CodeRegion,
+    instructions: Vec<Decoded>,
+}
+
+/// Basic blocks decoded once and reused, so `CPU::tick_cached` can skip fetching
+/// and decoding.
+///
+/// Each address has at most one block starting at it, tagged with the ROM bank
+/// it was decoded from. Running it with another bank mapped in decodes it again.
+/// Instructions are only rechecked once `Bus::page_writes` changes for their
+/// page: ROM ones against the bank that's mapped, and RAM ones against memory,
+/// with the block decoded again if they've been overwritten.
+pub struct BlockCache {
+    blocks: Vec<Block>,
+    /// The index in `blocks` of the block starting at each address.
+    starts: Box<[u32]>,
+    /// Slots in `blocks` left empty by invalidated blocks.
+    free: Vec<usize>,
+    /// The block being run, and the position of the next instruction in it.
+    cursor: Option<(usize, usize)>,
+}
+
+impl Default for BlockCache {
+    fn default() -> Self {
+        BlockCache {
+            blocks: vec![],
+            starts: vec![NO_BLOCK; 0x10000].into_boxed_slice(),
+            free: vec![],
+            cursor: None,
+        }
+    }
+}
+
+impl BlockCache {
+    /// The instruction at `pc`, or None if it has to be fetched as usual.
+    fn next<B: Bus>(&mut self, pc: u16, bus: &B) -> Option<Decoded> {
+        let page_writes = bus.page_writes(pc);
+
+        // Carrying on through a block whose page hasn't changed
+        if let Some((block, position)) = self.cursor {
+            if let Some(&decoded) = self.blocks[block].instructions.get(position) {
+                if decoded.pc == pc && decoded.page_writes == page_writes {
+                    self.cursor = Some((block, position + 1));
+                    return Some(decoded);
+                }
+            }
+        }
+
+        let region = bus.code_region(pc);
+
+        if region == CodeRegion::Uncached {
+            self.cursor = None;
+            return None;
+        }
+
+        let (mut block, mut position) = match self.cursor.take() {
+            Some((block, position)) if self.continues(block, position, region, pc) => {
+                (block, position)
+            }
+            _ => (self.block_at(region, pc, bus)?, 0),
+        };
+
+        let decoded = &mut self.blocks[block].instructions[position];
+
+        // ROM blocks are tagged with their bank, so only RAM needs reading again
+        if decoded.page_writes != page_writes {
+            if region != CodeRegion::Ram || decoded.matches(bus) {
+                decoded.page_writes = page_writes;
+            } else {
+                self.invalidate(block);
+
+                if let Some(stale) = self.start(pc) {
+                    self.invalidate(stale);
+                }
+
+                block = self.block_at(region, pc, bus)?;
+                position = 0;
+            }
+        }
+
+        self.cursor = Some((block, position + 1));
+
+        Some(self.blocks[block].instructions[position])
+    }
+
+    fn start(&self, pc: u16) -> Option<usize> {
+        match self.starts[pc as usize] {
+            NO_BLOCK => None,
+            block => Some(block as usize),
+        }
+    }
+
+    fn continues(&self, block: usize, position: usize, region: CodeRegion, pc: u16) -> bool {
+        let block = &self.blocks[block];
+
+        block.region == region
+            && block
+                .instructions
+                .get(position)
+                .is_some_and(|decoded| decoded.pc == pc)
+    }
+
+    /// The block starting at `pc`, decoding it if it hasn't been.
+    fn block_at<B: Bus>(&mut self, region: CodeRegion, pc: u16, bus: &B) -> Option<usize> {
+        if let Some(block) = self.start(pc) {
+            if self.blocks[block].region == region {
+                return Some(block);
+            }
+
+            self.invalidate(block);
+        }
+
+        let instructions = Self::decode_block(region, pc, bus);
+
+        if instructions.is_empty() {
+            return None;
+        }
+
+        let block = Block {
+            region,
+            instructions,
+        };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.blocks[index] = block;
+                index
+            }
+            None => {
+                self.blocks.push(block);
+                self.blocks.len() - 1
+            }
+        };
+
+        self.starts[pc as usize] = index as u32;
+
+        Some(index)
+    }
+
+    fn decode_block<B: Bus>(region: CodeRegion, start: u16, bus: &B) -> Vec<Decoded> {
+        let mut instructions = vec![];
+        let mut pc = start;
+
+        // Every byte `tick` reads has to come from the same region, and from one
+        // page for `page_writes` to cover it
+        while instructions.len() < MAX_BLOCK_LENGTH
+            && pc <= 0xFFFD
+            && (0..3).all(|offset| bus.code_region(pc + offset) == region)
+            && (region != CodeRegion::Ram || pc >> 8 == (pc + 2) >> 8)
+        {
+            let bytes = [
+                bus.read_byte(pc),
+                bus.read_byte(pc + 1),
+                bus.read_byte(pc + 2),
+            ];
+            let instruction = match bytes[0] {
+                CB_PREFIX => decode(CB_PREFIX, Some(bytes[1])),
+                op => decode(op, None),
+            };
+
+            if instruction.group == Group::Unimplemented {
+                break;
+            }
+
+            instructions.push(Decoded {
+                pc,
+                bytes,
+                instruction,
+                page_writes: bus.page_writes(pc),
+            });
+
+            let ends_block = instruction.group == Group::Branch
+                || matches!(
+                    instruction.instruction,
+                    Instruction::HALT | Instruction::STOP
+                );
+
+            if ends_block {
+                break;
+            }
+
+            pc = pc.wrapping_add(1 + instruction.size as u16);
+        }
+
+        instructions
+    }
+
+    fn invalidate(&mut self, block: usize) {
+        let block_ref = &mut self.blocks[block];
+
+        if let Some(first) = block_ref.instructions.first() {
+            self.starts[first.pc as usize] = NO_BLOCK;
+        }
+
+        block_ref.instructions.clear();
+        self.free.push(block);
+    }
+}
+
+impl CPU {
+    /// Runs the next instruction exactly like `tick`, taking it from `cache` when
+    /// it's already been decoded.
+    pub fn tick_cached<B: Bus>(
+        &mut self,
+        bus: &mut B,
+        cache: &mut BlockCache,
+    ) -> Result<u8, Error> {
+        let pc = *self.registers.pc.get_value();
+
+        // The HALT bug reads the next opcode without moving past it
+        if self.halt_bug {
+            return self.tick(bus);
+        }
+
+        let decoded = match cache.next(pc, bus) {
+            Some(decoded) => decoded,
+            None => return self.tick(bus),
+        };
+        let size = decoded.instruction.size;
+
+        self.gameboy_doc_debug(bus);
+        bus.set_instruction_pc(pc);
+
+        for (offset, &value) in decoded.bytes.iter().enumerate().take(size + 1) {
+            bus.on_access(Access {
+                address: pc + offset as u16,
+                value,
+                kind: AccessKind::Read,
+            });
+        }
+
+        self.run_fetched(
+            bus,
+            pc,
+            decoded.instruction,
+            [decoded.bytes[1], decoded.bytes[2]],
+            pc + 1 + size as u16,
+        )
+    }
+}
+
+#[cfg(test)]
+mod block_cache_test {
+    use crate::spec::block_cache::BlockCache;
+    use crate::spec::bus::{Bus, FlatBus, RecordingBus};
+    use crate::spec::cpu::{CPU, TCPU};
+    use crate::spec::register::TRegister;
+
+    fn load(program: &[u8]) -> (CPU, RecordingBus<FlatBus>) {
+        let mut cpu = CPU::new().unwrap();
+        let mut bus = RecordingBus::new(FlatBus::default());
+
+        for (offset, &byte) in program.iter().enumerate() {
+            bus.write_byte(0x0100 + offset as u16, byte);
+        }
+        cpu.registers_mut().pc.set_value(0x0100);
+        cpu.registers_mut().sp.set_value(0xD000);
+
+        (cpu, bus)
+    }
+
+    #[test]
+    fn self_modifying_code_runs_like_tick() {
+        let program = [
+            0x06, 0x00, // LD B, 0x00
+            0x0C, // INC C
+            0x21, 0x01, 0x01, // LD HL, 0x0101
+            0x34, // INC (HL)
+            0x18, 0xF7, // JR -9
+        ];
+        let (mut cpu, mut bus) = load(&program);
+        let (mut cached_cpu, mut cached_bus) = load(&program);
+        let mut cache = BlockCache::default();
+
+        for _ in 0..100 {
+            let cycles = cpu.tick(&mut bus).unwrap();
+            let cached_cycles = cached_cpu.tick_cached(&mut cached_bus, &mut cache).unwrap();
+
+            assert_eq!(cycles, cached_cycles);
+            assert_eq!(
+                format!("{}", cpu.registers()),
+                format!("{}", cached_cpu.registers())
+            );
+        }
+
+        assert_eq!(bus.accesses, cached_bus.accesses);
+        // 20 passes, each loading the operand the previous one left behind
+        assert_eq!(*cached_cpu.registers().b.get_value(), 19);
+        assert!(!cache.blocks.is_empty());
+    }
+}
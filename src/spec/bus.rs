@@ -0,0 +1,341 @@
+use crate::spec::hardware_registers::Interrupt;
+use crate::spec::mmu::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+/// A single memory access made by the CPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Access {
+    pub address: u16,
+    pub value: u8,
+    pub kind: AccessKind,
+}
+
+/// What the code at an address is stored in, which decides whether `BlockCache`
+/// can decode it once and reuse it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CodeRegion {
+    /// Fixed for as long as the ROM bank starting at this offset is mapped.
+    Rom(usize),
+    /// Read without side effects, but it can be written.
+    Ram,
+    /// Decoded every time it's run.
+    Uncached,
+}
+
+/// Everything the CPU is connected to.
+///
+/// `MMU` is the Game Boy's bus. Other implementations let the CPU run against
+/// flat RAM in tests, record what it does, or embed it somewhere else entirely.
+/// Besides memory, the CPU needs to see the interrupt lines, and STOP needs the
+/// joypad and KEY1; the defaults describe a bus with none of those attached.
+///
+/// The CPU makes its accesses through `cpu_read` and `cpu_write`, which report each
+/// one to `on_access`. `read_byte` is also used for debugging output, so it
+/// shouldn't have side effects.
+///
+/// Accesses can't fail, so that the common case stays a plain array index. A bus
+/// that wants to reject one, like `MMU` in strict mode, hands back the error from
+/// `take_error`, which the CPU checks after every instruction.
+pub trait Bus {
+    fn read_byte(&self, address: u16) -> u8;
+
+    fn write_byte(&mut self, address: u16, value: u8);
+
+    fn read_word(&self, address: u16) -> u16 {
+        let low = self.read_byte(address) as u16;
+        let high = self.read_byte(address.wrapping_add(1)) as u16;
+
+        high << 8 | low
+    }
+
+    fn write_word(&mut self, address: u16, value: u16) {
+        self.write_byte(address, value as u8);
+        self.write_byte(address.wrapping_add(1), (value >> 8) as u8)
+    }
+
+    /// An access that should have failed since this was last called.
+    fn take_error(&mut self) -> Option<Error> {
+        None
+    }
+
+    /// What the code at `address` is stored in.
+    fn code_region(&self, _address: u16) -> CodeRegion {
+        CodeRegion::Uncached
+    }
+
+    /// Changes whenever what's read from the 256 byte page holding `address` might
+    /// have, like a count of writes to it. `BlockCache` uses this to check that code
+    /// it decoded is still current without reading it again.
+    fn page_writes(&self, _address: u16) -> u32 {
+        0
+    }
+
+    /// Called with the address of each instruction before it's executed.
+    fn set_instruction_pc(&mut self, _pc: u16) {}
+
+    /// The highest priority interrupt that is both enabled and requested.
+    fn pending_interrupt(&self) -> Option<Interrupt> {
+        None
+    }
+
+    /// Whether any interrupt is both enabled and requested, regardless of IME.
+    fn interrupts_scheduled(&self) -> bool {
+        false
+    }
+
+    fn set_interrupt_bit(&mut self, _interrupt: Interrupt, _state: bool) {}
+
+    /// Whether a selected joypad button is held.
+    fn input_asserted(&self) -> bool {
+        false
+    }
+
+    fn speed_switch_armed(&self) -> bool {
+        false
+    }
+
+    fn switch_speed(&mut self) {}
+
+    /// STOP resets DIV from inside the CPU. That isn't an access the CPU makes, so
+    /// it takes no time and isn't reported to `on_access`.
+    fn reset_div(&mut self) {}
+
+    /// Called after every access the CPU makes, in order. Each takes one M-cycle
+    /// on hardware, so this is where a bus can keep the rest of the system in step
+    /// with the CPU within an instruction.
+    fn on_access(&mut self, _access: Access) {}
+
+    fn cpu_read(&mut self, address: u16) -> u8 {
+        let value = self.read_byte(address);
+        self.on_access(Access {
+            address,
+            value,
+            kind: AccessKind::Read,
+        });
+
+        value
+    }
+
+    fn cpu_write(&mut self, address: u16, value: u8) {
+        self.write_byte(address, value);
+        self.on_access(Access {
+            address,
+            value,
+            kind: AccessKind::Write,
+        });
+    }
+
+    /// Little endian, low byte first.
+    fn cpu_read_word(&mut self, address: u16) -> u16 {
+        let low = self.cpu_read(address) as u16;
+        let high = self.cpu_read(address.wrapping_add(1)) as u16;
+
+        high << 8 | low
+    }
+
+    /// Little endian, low byte first.
+    fn cpu_write_word(&mut self, address: u16, value: u16) {
+        self.cpu_write(address, value as u8);
+        self.cpu_write(address.wrapping_add(1), (value >> 8) as u8)
+    }
+}
+
+/// 64KB of RAM and nothing else, for running the CPU on its own. Write to it
+/// through `write_byte` if the CPU is going to run it with a `BlockCache`.
+pub struct FlatBus {
+    pub memory: Box<[u8]>,
+    page_writes: Box<[u32]>,
+}
+
+impl Default for FlatBus {
+    fn default() -> Self {
+        FlatBus {
+            memory: vec![0; 0x10000].into_boxed_slice(),
+            page_writes: vec![0; 0x100].into_boxed_slice(),
+        }
+    }
+}
+
+impl Bus for FlatBus {
+    fn read_byte(&self, address: u16) -> u8 {
+        self.memory[address as usize]
+    }
+
+    fn write_byte(&mut self, address: u16, value: u8) {
+        self.memory[address as usize] = value;
+        let writes = &mut self.page_writes[(address >> 8) as usize];
+        *writes = writes.wrapping_add(1);
+    }
+
+    fn code_region(&self, _address: u16) -> CodeRegion {
+        CodeRegion::Ram
+    }
+
+    fn page_writes(&self, address: u16) -> u32 {
+        self.page_writes[(address >> 8) as usize]
+    }
+}
+
+/// Wraps another bus, recording every access the CPU makes through it.
+pub struct RecordingBus<B: Bus> {
+    pub inner: B,
+    pub accesses: Vec<Access>,
+}
+
+impl<B: Bus> RecordingBus<B> {
+    pub fn new(inner: B) -> Self {
+        RecordingBus {
+            inner,
+            accesses: vec![],
+        }
+    }
+}
+
+impl<B: Bus> Bus for RecordingBus<B> {
+    fn read_byte(&self, address: u16) -> u8 {
+        self.inner.read_byte(address)
+    }
+
+    fn write_byte(&mut self, address: u16, value: u8) {
+        self.inner.write_byte(address, value)
+    }
+
+    fn take_error(&mut self) -> Option<Error> {
+        self.inner.take_error()
+    }
+
+    fn code_region(&self, address: u16) -> CodeRegion {
+        self.inner.code_region(address)
+    }
+
+    fn page_writes(&self, address: u16) -> u32 {
+        self.inner.page_writes(address)
+    }
+
+    fn set_instruction_pc(&mut self, pc: u16) {
+        self.inner.set_instruction_pc(pc)
+    }
+
+    fn pending_interrupt(&self) -> Option<Interrupt> {
+        self.inner.pending_interrupt()
+    }
+
+    fn interrupts_scheduled(&self) -> bool {
+        self.inner.interrupts_scheduled()
+    }
+
+    fn set_interrupt_bit(&mut self, interrupt: Interrupt, state: bool) {
+        self.inner.set_interrupt_bit(interrupt, state)
+    }
+
+    fn input_asserted(&self) -> bool {
+        self.inner.input_asserted()
+    }
+
+    fn speed_switch_armed(&self) -> bool {
+        self.inner.speed_switch_armed()
+    }
+
+    fn switch_speed(&mut self) {
+        self.inner.switch_speed()
+    }
+
+    fn reset_div(&mut self) {
+        self.inner.reset_div()
+    }
+
+    fn on_access(&mut self, access: Access) {
+        self.accesses.push(access);
+        self.inner.on_access(access);
+    }
+
+    // The inner bus may do more around an access than `on_access` sees
+    fn cpu_read(&mut self, address: u16) -> u8 {
+        let value = self.inner.cpu_read(address);
+        self.accesses.push(Access {
+            address,
+            value,
+            kind: AccessKind::Read,
+        });
+
+        value
+    }
+
+    fn cpu_write(&mut self, address: u16, value: u8) {
+        self.inner.cpu_write(address, value);
+        self.accesses.push(Access {
+            address,
+            value,
+            kind: AccessKind::Write,
+        });
+    }
+}
+
+#[cfg(test)]
+mod bus_test {
+    use crate::spec::bus::{Access, AccessKind, Bus, FlatBus, RecordingBus};
+    use crate::spec::cpu::{CPU, TCPU};
+    use crate::spec::register::TRegister;
+
+    #[test]
+    fn recording_bus_sees_every_cpu_access_in_order() {
+        let mut cpu = CPU::new().unwrap();
+        let mut bus = RecordingBus::new(FlatBus::default());
+        // CALL 0x1234
+        bus.write_byte(0x0100, 0xCD);
+        bus.write_word(0x0101, 0x1234);
+        cpu.registers_mut().pc.set_value(0x0100);
+        cpu.registers_mut().sp.set_value(0xD000);
+
+        cpu.tick(&mut bus).unwrap();
+
+        let access = |address, value, kind| Access {
+            address,
+            value,
+            kind,
+        };
+        assert_eq!(
+            bus.accesses,
+            vec![
+                access(0x0100, 0xCD, AccessKind::Read),
+                access(0x0101, 0x34, AccessKind::Read),
+                access(0x0102, 0x12, AccessKind::Read),
+                access(0xCFFF, 0x01, AccessKind::Write),
+                access(0xCFFE, 0x03, AccessKind::Write),
+            ]
+        );
+    }
+
+    #[test]
+    fn stop_only_reads_its_two_bytes() {
+        let mut cpu = CPU::new().unwrap();
+        let mut bus = RecordingBus::new(FlatBus::default());
+        // STOP
+        bus.write_word(0x0100, 0x0010);
+        cpu.registers_mut().pc.set_value(0x0100);
+
+        cpu.tick(&mut bus).unwrap();
+
+        assert!(cpu.stopped);
+        assert_eq!(
+            bus.accesses,
+            vec![
+                Access {
+                    address: 0x0100,
+                    value: 0x10,
+                    kind: AccessKind::Read,
+                },
+                Access {
+                    address: 0x0101,
+                    value: 0x00,
+                    kind: AccessKind::Read,
+                },
+            ]
+        );
+    }
+}
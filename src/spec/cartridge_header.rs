@@ -21,11 +21,13 @@ pub mod cartridge_header_address {
     pub const CARTRIDGE_RAM_SIZE: usize = 0x149;
     pub const CARTRIDGE_ROM_SIZE: usize = 0x148;
     pub const CARTRIDGE_TYPE: usize = 0x147;
+    pub const CGB_FLAG: usize = 0x143;
     pub const GAME_TITLE: usize = 0x134;
     pub const ENTRY: usize = 0x102;
 }
 
 pub const GAME_TITLE_LENGTH: usize = 0xF;
+pub const HEADER_END: usize = 0x150;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum CartridgeType {
@@ -143,6 +145,7 @@ pub struct Cartridge {
     pub game_title: String,
     pub rom_size: usize,
     pub ram_size: usize,
+    pub cgb: bool,
 }
 
 impl fmt::Display for Cartridge {
@@ -157,6 +160,10 @@ impl fmt::Display for Cartridge {
 
 impl Cartridge {
     pub fn new(buffer: &[u8]) -> Result<Self, CartridgeError> {
+        if buffer.len() < HEADER_END {
+            return Err(CartridgeError::BadRomData);
+        }
+
         let game_title = match str::from_utf8(
             &buffer[cartridge_header_address::GAME_TITLE
                 ..cartridge_header_address::GAME_TITLE + GAME_TITLE_LENGTH],
@@ -182,6 +189,7 @@ impl Cartridge {
             game_title: game_title.to_string(),
             rom_size,
             ram_size,
+            cgb: buffer[cartridge_header_address::CGB_FLAG] & 0x80 != 0,
         })
     }
 
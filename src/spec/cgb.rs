@@ -0,0 +1,138 @@
+use crate::spec::clock::SpeedMode;
+use crate::spec::hardware_registers::io_address::{
+    BCPD, BCPS, HDMA1, HDMA5, KEY1, OCPD, OCPS, OPRI, RP, SVBK, VBK,
+};
+use crate::spec::hardware_registers::IoHandler;
+use crate::spec::mmu::OPEN_BUS;
+use crate::spec::scheduler::Scheduler;
+
+const PALETTE_MEMORY_SIZE: usize = 64;
+const PALETTE_AUTO_INCREMENT: u8 = 0x80;
+
+/// Background or object palette memory, read and written a byte at a time through
+/// an index register and a data register.
+#[derive(Hash)]
+pub struct PaletteMemory {
+    index: u8,
+    data: [u8; PALETTE_MEMORY_SIZE],
+}
+
+impl Default for PaletteMemory {
+    fn default() -> Self {
+        PaletteMemory {
+            index: 0,
+            data: [0; PALETTE_MEMORY_SIZE],
+        }
+    }
+}
+
+impl PaletteMemory {
+    fn read(&self) -> u8 {
+        self.data[(self.index & 0x3F) as usize]
+    }
+
+    fn write(&mut self, value: u8) {
+        self.data[(self.index & 0x3F) as usize] = value;
+
+        if self.index & PALETTE_AUTO_INCREMENT != 0 {
+            self.index = PALETTE_AUTO_INCREMENT | (self.index.wrapping_add(1) & 0x3F);
+        }
+    }
+}
+
+/// Registers that only exist on the CGB. On a DMG, or for cartridges that don't
+/// ask for CGB features, they read as open bus and ignore writes.
+///
+/// Only the speed switch is emulated. VRAM and WRAM banking, HDMA, infrared and
+/// the colour palettes keep what's written to them so it reads back, but nothing
+/// else looks at them yet.
+#[derive(Default, Hash)]
+pub struct CgbRegisters {
+    enabled: bool,
+    speed_switch_armed: bool,
+    speed_mode: SpeedMode,
+    vram_bank: u8,
+    hdma: [u8; 5],
+    infrared: u8,
+    background_palettes: PaletteMemory,
+    object_palettes: PaletteMemory,
+    object_priority: u8,
+    wram_bank: u8,
+}
+
+impl CgbRegisters {
+    pub fn new(enabled: bool) -> Self {
+        CgbRegisters {
+            enabled,
+            ..CgbRegisters::default()
+        }
+    }
+
+    pub fn speed_mode(&self) -> SpeedMode {
+        self.speed_mode
+    }
+
+    pub fn speed_switch_armed(&self) -> bool {
+        self.speed_switch_armed
+    }
+
+    /// Toggles between single and double speed, as requested through KEY1.
+    pub fn switch_speed(&mut self) {
+        self.speed_mode = match self.speed_mode {
+            SpeedMode::Single => SpeedMode::Double,
+            SpeedMode::Double => SpeedMode::Single,
+        };
+        self.speed_switch_armed = false;
+    }
+}
+
+impl IoHandler for CgbRegisters {
+    fn read_register(&self, address: u16, _scheduler: &Scheduler) -> u8 {
+        if !self.enabled {
+            return OPEN_BUS;
+        }
+
+        match address {
+            KEY1 => {
+                let current_speed = match self.speed_mode {
+                    SpeedMode::Single => 0,
+                    SpeedMode::Double => 0x80,
+                };
+
+                current_speed | self.speed_switch_armed as u8
+            }
+            VBK => self.vram_bank,
+            HDMA1..=HDMA5 => self.hdma[(address - HDMA1) as usize],
+            RP => self.infrared,
+            BCPS => self.background_palettes.index,
+            BCPD => self.background_palettes.read(),
+            OCPS => self.object_palettes.index,
+            OCPD => self.object_palettes.read(),
+            OPRI => self.object_priority,
+            SVBK => self.wram_bank,
+            _ => unreachable!("CGB registers do not own address {:X}", address),
+        }
+    }
+
+    fn write_register(&mut self, address: u16, value: u8, _scheduler: &mut Scheduler) -> u8 {
+        if !self.enabled {
+            return 0;
+        }
+
+        match address {
+            KEY1 => self.speed_switch_armed = (value & 1) == 1,
+            VBK => self.vram_bank = value,
+            HDMA1..=HDMA5 => self.hdma[(address - HDMA1) as usize] = value,
+            RP => self.infrared = value,
+            BCPS => self.background_palettes.index = value,
+            BCPD => self.background_palettes.write(value),
+            OCPS => self.object_palettes.index = value,
+            OCPD => self.object_palettes.write(value),
+            OPRI => self.object_priority = value,
+            SVBK => self.wram_bank = value,
+            _ => unreachable!("CGB registers do not own address {:X}", address),
+        }
+
+        0
+    }
+}
@@ -1,30 +1,37 @@
-use crate::dasm::{DasmError, InstructionData};
+use crate::dasm::{decode, DasmError, InstructionData};
 
-use crate::spec::mmu::{Error as MmuError, MMU};
-use crate::spec::mnemonic::Mnemonic;
-use crate::spec::opcode::Instruction;
+use crate::spec::bus::{Access, AccessKind, Bus};
+use crate::spec::mmu::Error as MmuError;
+use crate::spec::opcode::{Instruction, CB_PREFIX};
 
 use crate::debug_logger::{cpu_logger::CPU_LOGGER, DebugLogger};
 use crate::spec::register::{RegisterError, Registers, TRegister};
-use std::convert::TryFrom;
 
 use std::num::Wrapping;
 
 pub trait TCPU {
     type E;
-    fn tick(&mut self, mmu: &mut MMU) -> Result<u8, Self::E>;
+    fn tick<B: Bus>(&mut self, bus: &mut B) -> Result<u8, Self::E>;
 }
 
 pub trait TStackable {
-    fn push_stack_byte(&mut self, value: u8, mmu: &mut MMU) -> Result<(), Error>;
-    fn push_stack_word(&mut self, value: u16, mmu: &mut MMU) -> Result<(), Error>;
-    fn pop_stack_byte(&mut self, mmu: &mut MMU) -> Result<u8, Error>;
-    fn pop_stack_word(&mut self, mmu: &mut MMU) -> Result<u16, Error>;
+    fn push_stack_byte<B: Bus>(&mut self, value: u8, bus: &mut B) -> Result<(), Error>;
+    fn push_stack_word<B: Bus>(&mut self, value: u16, bus: &mut B) -> Result<(), Error>;
+    fn pop_stack_byte<B: Bus>(&mut self, bus: &mut B) -> Result<u8, Error>;
+    fn pop_stack_word<B: Bus>(&mut self, bus: &mut B) -> Result<u16, Error>;
 }
 
+#[derive(Hash)]
 pub struct CPU {
     pub(crate) registers: Registers,
     pub(crate) halt: bool,
+    pub(crate) ime: bool,
+    /// Instructions left to execute before a pending EI sets IME.
+    pub(crate) ei_delay: u8,
+    pub(crate) halt_bug: bool,
+    pub(crate) stopped: bool,
+    /// M-cycles the CPU is paused for after a speed switch.
+    pub(crate) speed_switch_stall: usize,
 }
 
 #[derive(Debug)]
@@ -54,55 +61,62 @@ impl From<MmuError> for Error {
 impl TCPU for CPU {
     type E = Error;
 
-    fn tick(&mut self, mmu: &mut MMU) -> Result<u8, Error> {
-        self.gameboy_doc_debug(mmu);
+    fn tick<B: Bus>(&mut self, bus: &mut B) -> Result<u8, Error> {
+        self.gameboy_doc_debug(bus);
 
         let last_pc = *self.registers.pc.get_value();
-        let opcode = self.fetch(mmu)?;
+        bus.set_instruction_pc(last_pc);
+        let (opcode, fetched) = self.fetch(bus)?;
+        let operands_at = *self.registers.pc.get_value();
         let data = [
-            mmu.read_byte(*self.registers.pc.get_value())
-                .map_err(Error::MmuError)?,
-            mmu.read_byte((Wrapping(*self.registers.pc.get_value()) + Wrapping(1)).0)
-                .map_err(Error::MmuError)?,
+            bus.read_byte(operands_at),
+            bus.read_byte(operands_at.wrapping_add(1)),
         ];
-        CPU_LOGGER.log("PC", || {
-            println!("[PC: {:#X}] Op: {}, Dat: [{:X?}]", last_pc, opcode, data)
-        });
-        self.registers
-            .pc
-            .set_value(*self.registers.pc.get_value() + opcode.size as u16);
-        let cycles = self.execute(&opcode, &data, mmu)?;
-        CPU_LOGGER.log("REG", || println!("\t{}", self.registers));
-        Ok(cycles)
+
+        // Operands are read ahead without side effects, so report the ones that
+        // belong to this instruction. For CB instructions, fetching covered them.
+        for (offset, &value) in data.iter().enumerate().take(opcode.size + 1 - fetched) {
+            bus.on_access(Access {
+                address: operands_at.wrapping_add(offset as u16),
+                value,
+                kind: AccessKind::Read,
+            });
+        }
+        let next_pc = operands_at.wrapping_add(opcode.size as u16);
+
+        self.run_fetched(bus, last_pc, opcode, data, next_pc)
     }
 }
 
 impl TStackable for CPU {
-    fn push_stack_byte(&mut self, value: u8, mmu: &mut MMU) -> Result<(), Error> {
+    fn push_stack_byte<B: Bus>(&mut self, value: u8, bus: &mut B) -> Result<(), Error> {
         self.registers
             .sp
             .update_value_checked(|sp| {
-                mmu.write_byte(*sp, value)?;
+                bus.cpu_write(*sp, value);
                 Ok(sp.checked_sub(1))
             })
             .map_err(Error::RegisterError)
     }
 
-    fn push_stack_word(&mut self, value: u16, mmu: &mut MMU) -> Result<(), Error> {
+    fn push_stack_word<B: Bus>(&mut self, value: u16, bus: &mut B) -> Result<(), Error> {
         self.registers
             .sp
             .update_value_checked(|sp| Ok(sp.checked_sub(2)))?;
-        mmu.write_word(*self.registers.sp.get_value(), value)?;
+        // The high byte is pushed first
+        let sp = *self.registers.sp.get_value();
+        bus.cpu_write(sp.wrapping_add(1), (value >> 8) as u8);
+        bus.cpu_write(sp, value as u8);
 
         Ok(())
     }
 
-    fn pop_stack_byte(&mut self, _mmu: &mut MMU) -> Result<u8, Error> {
+    fn pop_stack_byte<B: Bus>(&mut self, _bus: &mut B) -> Result<u8, Error> {
         unimplemented!()
     }
 
-    fn pop_stack_word(&mut self, mmu: &mut MMU) -> Result<u16, Error> {
-        let stack_val = mmu.read_word(*self.registers.sp.get_value())?;
+    fn pop_stack_word<B: Bus>(&mut self, bus: &mut B) -> Result<u16, Error> {
+        let stack_val = bus.cpu_read_word(*self.registers.sp.get_value());
         self.registers
             .sp
             .update_value_checked(|sp| Ok(sp.checked_add(2)))?;
@@ -112,97 +126,214 @@ impl TStackable for CPU {
 }
 
 impl CPU {
-    fn increment_pc(&mut self) -> Result<u16, Error> {
+    fn increment_pc(&mut self) -> u16 {
         let next = *self.registers.pc.get_value();
-        self.registers
-            .pc
-            .update_value_checked(|pc| Ok(pc.checked_add(1)))
-            .map_err(Error::RegisterError)?;
 
-        Ok(next)
+        if self.halt_bug {
+            self.halt_bug = false;
+            return next;
+        }
+
+        self.registers.pc.set_value(next.wrapping_add(1));
+
+        next
     }
 
-    fn fetch(&mut self, mmu: &MMU) -> Result<InstructionData, Error> {
-        let pc = self.increment_pc()?;
-        let op = mmu.read_byte(pc).map_err(Error::MmuError)?;
-        let cb_byte = match op {
-            0xCB => Some(mmu.read_byte(pc + 1).map_err(Error::MmuError)?),
-            _ => None,
-        };
+    /// Returns the decoded instruction and how many bytes of it were read.
+    fn fetch<B: Bus>(&mut self, bus: &mut B) -> Result<(&'static InstructionData, usize), Error> {
+        let pc = self.increment_pc();
+        let op = bus.cpu_read(pc);
 
-        InstructionData::try_from((op, cb_byte)).map_err(Error::DecodeError)
+        match op {
+            CB_PREFIX => {
+                let cb_byte = bus.cpu_read(pc.wrapping_add(1));
+                Ok((decode(op, Some(cb_byte)), 2))
+            }
+            _ => Ok((decode(op, None), 1)),
+        }
     }
 
-    fn execute(
+    /// Runs an instruction once it's been read from `pc`, moving PC to `next_pc` first.
+    pub(crate) fn run_fetched<B: Bus>(
+        &mut self,
+        bus: &mut B,
+        pc: u16,
+        opcode: &InstructionData,
+        data: [u8; 2],
+        next_pc: u16,
+    ) -> Result<u8, Error> {
+        CPU_LOGGER.log("PC", || {
+            println!("[PC: {:#X}] Op: {}, Dat: [{:X?}]", pc, opcode, data)
+        });
+        self.registers.pc.set_value(next_pc);
+        let cycles = self.execute(opcode, &data, bus)?;
+
+        if let Some(error) = bus.take_error() {
+            return Err(Error::MmuError(error));
+        }
+
+        self.update_ime();
+        CPU_LOGGER.log("REG", || println!("\t{}", self.registers));
+        Ok(cycles)
+    }
+
+    /// Runs the handler for the instruction, with the operands decoded along with it.
+    fn execute<B: Bus>(
         &mut self,
         instruction_data: &InstructionData,
         opcode_data: &[u8; 2],
-        mmu: &mut MMU,
+        bus: &mut B,
     ) -> Result<u8, Error> {
-        let result = match instruction_data.mnemonic {
-            Mnemonic::LD | Mnemonic::LDHL => self.evaluate_ld(instruction_data, opcode_data, mmu),
-            Mnemonic::PUSH | Mnemonic::POP => {
-                self.evaluate_stack_op(instruction_data, opcode_data, mmu)
+        match instruction_data.instruction {
+            Instruction::LD_RR => self.ld_rr(&instruction_data.operands),
+            Instruction::LD_RN => self.ld_rn(&instruction_data.operands, opcode_data),
+            Instruction::LD_RHL => self.ld_rhl(&instruction_data.operands, bus),
+            Instruction::LD_HLR => self.ld_hlr(&instruction_data.operands, bus),
+            Instruction::LD_HLN => self.ld_hln(opcode_data, bus),
+            Instruction::LD_ABC => self.ld_abc(bus),
+            Instruction::LD_ADE => self.ld_ade(bus),
+            Instruction::LD_AN => self.ld_an(opcode_data, bus),
+            Instruction::LD_ANN => self.ld_ann(opcode_data, bus),
+            Instruction::LD_BCA => self.ld_bca(bus),
+            Instruction::LD_DEA => self.ld_dea(bus),
+            Instruction::LD_NA => self.ld_na(opcode_data, bus),
+            Instruction::LD_NNA => self.ld_nna(opcode_data, bus),
+            Instruction::LD_AFF00C => self.ld_aff00c(bus),
+            Instruction::LD_FF00CA => self.ld_ff00ca(bus),
+            Instruction::LD_HLIA => self.ld_hlia(bus),
+            Instruction::LD_AHLI => self.ld_ahli(bus),
+            Instruction::LD_HLDA => self.ld_hlda(bus),
+            Instruction::LD_AHLD => self.ld_ahld(bus),
+            Instruction::LD_RRNN => self.ld_rrnn(&instruction_data.operands, opcode_data),
+            Instruction::LD_SPHL => self.ld_sphl(),
+            Instruction::LD_SPDD => self.ld_spdd(opcode_data, bus),
+            Instruction::LDHL => self.ldhl(opcode_data),
+            Instruction::PUSH_RR => self.push_rr(&instruction_data.operands, bus),
+            Instruction::POP_RR => self.pop_rr(&instruction_data.operands, bus),
+            Instruction::ADD_AR => self.add_ar(&instruction_data.operands),
+            Instruction::ADD_AN => self.add_an(opcode_data),
+            Instruction::ADD_AHL => self.add_ahl(bus),
+            Instruction::ADC_AR => self.adc_ar(&instruction_data.operands),
+            Instruction::ADC_AN => self.adc_an(opcode_data),
+            Instruction::ADC_AHL => self.adc_ahl(bus),
+            Instruction::SUB_R => self.sub_r(&instruction_data.operands),
+            Instruction::SUB_N => self.sub_n(opcode_data),
+            Instruction::SUB_HL => self.sub_hl(bus),
+            Instruction::SBC_AR => self.sbc_ar(&instruction_data.operands),
+            Instruction::SBC_AN => self.sbc_an(opcode_data),
+            Instruction::SBC_AHL => self.sbc_ahl(bus),
+            Instruction::AND_R => self.and_r(&instruction_data.operands),
+            Instruction::AND_N => self.and_n(opcode_data),
+            Instruction::AND_HL => self.and_hl(bus),
+            Instruction::XOR_R => self.xor_r(&instruction_data.operands),
+            Instruction::XOR_N => self.xor_n(opcode_data),
+            Instruction::XOR_HL => self.xor_hl(bus),
+            Instruction::OR_R => self.or_r(&instruction_data.operands),
+            Instruction::OR_N => self.or_n(opcode_data),
+            Instruction::OR_HL => self.or_hl(bus),
+            Instruction::CP_R => self.cp_r(&instruction_data.operands),
+            Instruction::CP_N => self.cp_n(opcode_data),
+            Instruction::CP_HL => self.cp_hl(bus),
+            Instruction::INC_R => self.inc_r(&instruction_data.operands),
+            Instruction::INC_HL => self.inc_hl(bus),
+            Instruction::DEC_R => self.dec_r(&instruction_data.operands),
+            Instruction::DEC_HL => self.dec_hl(bus),
+            Instruction::DAA => self.daa(),
+            Instruction::CPL => self.cpl(),
+            Instruction::ADD_HLRR => self.add_hlrr(&instruction_data.operands),
+            Instruction::ADD_SPN => self.add_spn(opcode_data),
+            Instruction::INC_RR => self.inc_rr(&instruction_data.operands),
+            Instruction::DEC_RR => self.dec_rr(&instruction_data.operands),
+            Instruction::RLCA => self.rlca(),
+            Instruction::RLA => self.rla(),
+            Instruction::RRCA => self.rrca(),
+            Instruction::RRA => self.rra(),
+            Instruction::RLC_R => self.rlc_r(&instruction_data.operands),
+            Instruction::RLC_HL => self.rlc_hl(bus),
+            Instruction::RL_R => self.rl_r(&instruction_data.operands),
+            Instruction::RL_HL => self.rl_hl(bus),
+            Instruction::RRC_R => self.rrc_r(&instruction_data.operands),
+            Instruction::RRC_HL => self.rrc_hl(bus),
+            Instruction::RR_R => self.rr_r(&instruction_data.operands),
+            Instruction::RR_HL => self.rr_hl(bus),
+            Instruction::SLA_R => self.sla_r(&instruction_data.operands),
+            Instruction::SLA_HL => self.sla_hl(bus),
+            Instruction::SWAP_R => self.swap_r(&instruction_data.operands),
+            Instruction::SWAP_HL => self.swap_hl(bus),
+            Instruction::SRA_R => self.sra_r(&instruction_data.operands),
+            Instruction::SRA_HL => self.sra_hl(bus),
+            Instruction::SRL_R => self.srl_r(&instruction_data.operands),
+            Instruction::SRL_HL => self.srl_hl(bus),
+            Instruction::BIT_NR => self.bit_nr(&instruction_data.operands),
+            Instruction::BIT_NHL => self.bit_nhl(&instruction_data.operands, bus),
+            Instruction::SET_NR => self.set_nr(&instruction_data.operands),
+            Instruction::SET_NHL => self.set_nhl(&instruction_data.operands, bus),
+            Instruction::RES_NR => self.res_nr(&instruction_data.operands),
+            Instruction::RES_NHL => self.res_nhl(&instruction_data.operands, bus),
+            Instruction::CCF => self.ccf(),
+            Instruction::SCF => self.scf(),
+            Instruction::NOP => self.nop(),
+            Instruction::HALT => self.halt(bus),
+            Instruction::DI => self.di(),
+            Instruction::EI => self.ei(),
+            Instruction::JP_NN => self.jp_nn(opcode_data),
+            Instruction::JP_HL => self.jp_hl(),
+            Instruction::JP_FNN => self.jp_fnn(&instruction_data.operands, opcode_data),
+            Instruction::JR_PCDD => self.jr_pcdd(opcode_data),
+            Instruction::JR_FPCDD => self.jr_fpcdd(&instruction_data.operands, opcode_data),
+            Instruction::CALL_NN => self.call_nn(opcode_data, bus),
+            Instruction::CALL_FNN => self.call_fnn(&instruction_data.operands, opcode_data, bus),
+            Instruction::RET => self.ret(bus),
+            Instruction::RET_F => self.ret_f(&instruction_data.operands, bus),
+            Instruction::RETI => self.reti(bus),
+            Instruction::RST => self.rst(&instruction_data.operands, bus),
+            Instruction::STOP => {
+                self.stop(bus);
+                Ok(1)
+            }
+            Instruction::UNIMPLEMENTED => Ok(0),
+        }
+    }
+
+    fn update_ime(&mut self) {
+        if self.ei_delay > 0 {
+            self.ei_delay -= 1;
+
+            if self.ei_delay == 0 {
+                self.ime = true;
             }
-            Mnemonic::ADD
-            | Mnemonic::ADC
-            | Mnemonic::SUB
-            | Mnemonic::SBC
-            | Mnemonic::AND
-            | Mnemonic::XOR
-            | Mnemonic::OR
-            | Mnemonic::CP
-            | Mnemonic::INC
-            | Mnemonic::DEC
-            | Mnemonic::DAA
-            | Mnemonic::CPL => self.evaluate_alu(instruction_data, opcode_data, mmu),
-            Mnemonic::RLCA
-            | Mnemonic::RLA
-            | Mnemonic::RRCA
-            | Mnemonic::RRA
-            | Mnemonic::RLC
-            | Mnemonic::RL
-            | Mnemonic::RRC
-            | Mnemonic::RR
-            | Mnemonic::SLA
-            | Mnemonic::SWAP
-            | Mnemonic::SRA
-            | Mnemonic::SET
-            | Mnemonic::BIT
-            | Mnemonic::RES
-            | Mnemonic::SRL => self.evaluate_bitwise(instruction_data, opcode_data, mmu),
-            Mnemonic::CCF
-            | Mnemonic::SCF
-            | Mnemonic::NOP
-            | Mnemonic::HALT
-            | Mnemonic::STOP
-            | Mnemonic::DI
-            | Mnemonic::EI => self.evaluate_control(instruction_data, opcode_data, mmu),
-            Mnemonic::JP
-            | Mnemonic::JR
-            | Mnemonic::CALL
-            | Mnemonic::RET
-            | Mnemonic::RETI
-            | Mnemonic::RST
-            | Mnemonic::DB
-            | Mnemonic::DW => self.evaluate_branch(instruction_data, opcode_data, mmu),
-            Mnemonic::UNIMPLEMENTED => Ok(0),
-        }?;
-        Ok(result)
-    }
-
-    pub fn handle_interrupts(&mut self, mmu: &mut MMU) -> Result<u8, Error> {
-        if let Some(interrupt) = mmu.interrupts_enabled()? {
+        }
+    }
+
+    pub fn handle_interrupts<B: Bus>(&mut self, bus: &mut B) -> Result<u8, Error> {
+        if !self.ime {
+            return Ok(0);
+        }
+
+        if let Some(interrupt) = bus.pending_interrupt() {
             CPU_LOGGER.log("INTS", || println!("Handling Interrupt: {:?}", interrupt));
 
             let isr = interrupt.get_isr_location();
 
             CPU_LOGGER.log("INTS", || println!("Jumping to {:X}", isr));
 
-            self.push_stack_word(*self.registers.pc.get_value(), mmu)?;
+            // EI followed by a HALT that triggered the bug returns to the HALT itself
+            let return_address = if self.halt_bug {
+                self.halt_bug = false;
+                self.registers.pc.get_value().wrapping_sub(1)
+            } else {
+                *self.registers.pc.get_value()
+            };
+
+            self.push_stack_word(return_address, bus)?;
+
+            if let Some(error) = bus.take_error() {
+                return Err(Error::MmuError(error));
+            }
+
             self.registers.pc.set_value(isr);
-            mmu.write_interrupt_enable_reg(false);
-            mmu.set_interrupt_bit(interrupt, false)?;
+            self.ime = false;
+            bus.set_interrupt_bit(interrupt, false);
 
             return Ok(5);
         }
@@ -210,17 +341,13 @@ impl CPU {
         Ok(0)
     }
 
-    pub fn gameboy_doc_debug(&self, mmu: &MMU) {
+    pub fn gameboy_doc_debug<B: Bus>(&self, bus: &B) {
         CPU_LOGGER.log("GB_DOC", || {
             let pc_mem = [
-                mmu.read_byte(*self.registers.pc.get_value())
-                    .map_err(Error::MmuError).unwrap(),
-                mmu.read_byte((Wrapping(*self.registers.pc.get_value()) + Wrapping(1)).0)
-                    .map_err(Error::MmuError).unwrap(),
-                mmu.read_byte((Wrapping(*self.registers.pc.get_value()) + Wrapping(2)).0)
-                    .map_err(Error::MmuError).unwrap(),
-                mmu.read_byte((Wrapping(*self.registers.pc.get_value()) + Wrapping(3)).0)
-                    .map_err(Error::MmuError).unwrap(),
+                bus.read_byte(*self.registers.pc.get_value()),
+                bus.read_byte((Wrapping(*self.registers.pc.get_value()) + Wrapping(1)).0),
+                bus.read_byte((Wrapping(*self.registers.pc.get_value()) + Wrapping(2)).0),
+                bus.read_byte((Wrapping(*self.registers.pc.get_value()) + Wrapping(3)).0),
 
             ];
             println!(
@@ -243,10 +370,32 @@ impl CPU {
         });
     }
 
+    pub fn registers(&self) -> &Registers {
+        &self.registers
+    }
+
+    pub fn registers_mut(&mut self) -> &mut Registers {
+        &mut self.registers
+    }
+
+    pub fn ime(&self) -> bool {
+        self.ime
+    }
+
+    pub fn set_ime(&mut self, ime: bool) {
+        self.ime = ime;
+        self.ei_delay = 0;
+    }
+
     pub fn new() -> Result<CPU, Error> {
         Ok(CPU {
             registers: Registers::new(),
             halt: false,
+            ime: false,
+            ei_delay: 0,
+            halt_bug: false,
+            stopped: false,
+            speed_switch_stall: 0,
         })
     }
 }
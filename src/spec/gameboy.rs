@@ -1,19 +1,55 @@
+use crate::device::Device;
+use crate::movie::{Frame, Movie, Recorder, Restart};
+use crate::spec::block_cache::BlockCache;
 use crate::spec::cartridge_header::{Cartridge, CartridgeError};
-use crate::spec::clock::{Clock, TimerError};
+use crate::spec::clock::{Clock, SpeedMode};
 use crate::spec::cpu::{Error as CpuError, CPU, TCPU};
+use crate::spec::joypad::Button;
 use crate::spec::mmu::{Error as MmuError, MMU};
+use crate::spec::register::{Registers, TRegister};
+use crate::util::hash::Fnv1a;
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
 
-pub enum Peripheral<'a> {
-    SerialPort(Box<dyn FnMut(Option<char>) + 'a>),
-}
+const LD_B_B: u8 = 0x40;
 
-pub struct GameBoy<'a> {
-    #[allow(dead_code)]
+pub struct GameBoy {
     cartridge: Cartridge,
     clock: Clock,
     cpu: CPU,
-    mmu: MMU,
-    peripherals: Vec<Peripheral<'a>>,
+    pub(crate) mmu: MMU,
+    breakpoints: Vec<u16>,
+    software_breakpoints: bool,
+    block_cache: Option<BlockCache>,
+    recorder: Option<Recorder>,
+    /// Only turned off by tests, to check that skipping ahead while halted changes
+    /// nothing.
+    skip_halted: bool,
+}
+
+/// Why a run stopped.
+#[derive(Debug)]
+pub enum StopReason {
+    /// The requested number of t-cycles have elapsed.
+    CyclesElapsed,
+    /// A frame was completed.
+    Frame,
+    /// The condition passed to `run_until` was met.
+    Condition,
+    /// The next instruction is at a breakpoint.
+    Breakpoint(u16),
+    /// The next instruction is `LD B,B`, with software breakpoints on.
+    SoftwareBreakpoint(u16),
+    Error(GameBoyError),
+}
+
+/// What happened during a run.
+#[derive(Debug)]
+pub struct RunResult {
+    pub t_cycles: u64,
+    /// Frames that entered VBlank during the run.
+    pub frames: u64,
+    pub stop: StopReason,
 }
 
 #[derive(Debug, Default)]
@@ -23,7 +59,9 @@ pub enum GameBoyError {
     Cpu(CpuError),
     Mmu(MmuError),
     Cartridge(CartridgeError),
-    Timer(TimerError),
+    /// Only `run_frame` applies recorded input, so nothing else can run while
+    /// recording.
+    Recording,
 }
 
 impl From<CpuError> for GameBoyError {
@@ -44,13 +82,7 @@ impl From<CartridgeError> for GameBoyError {
     }
 }
 
-impl From<TimerError> for GameBoyError {
-    fn from(e: TimerError) -> Self {
-        GameBoyError::Timer(e)
-    }
-}
-
-impl<'a> GameBoy<'a> {
+impl GameBoy {
     pub fn new(rom: &[u8]) -> Result<GameBoy, GameBoyError> {
         // println!("Loading Cartridge Header");
         let cartridge = Cartridge::new(rom)?;
@@ -58,7 +90,7 @@ impl<'a> GameBoy<'a> {
         // println!("Initializing Z80 CPU");
         let cpu = CPU::new()?;
         // println!("Initializing MMU");
-        let mmu = MMU::new(rom, &cartridge.cartridge_type)?;
+        let mmu = MMU::new(rom, &cartridge)?;
         // println!("Initializing Clock");
         let clock = Clock::default();
         // println!("OK");
@@ -68,62 +100,829 @@ impl<'a> GameBoy<'a> {
             mmu,
             clock,
             cartridge,
-            peripherals: vec![],
+            breakpoints: vec![],
+            software_breakpoints: false,
+            block_cache: None,
+            recorder: None,
+            skip_halted: true,
         })
     }
 
+    /// Runs one instruction, interrupt dispatch or halted stretch, returning the
+    /// M-cycles it took.
     pub fn cycle(&mut self) -> Result<usize, GameBoyError> {
-        // The effect of ei is delayed by one instruction.
-        // This means that ei followed immediately by di does not allow any interrupts between them.
-        let can_handle_this_cycle = self.mmu.enable_interrupts;
+        if self.recorder.is_some() {
+            return Err(GameBoyError::Recording);
+        }
 
-        if !self.cpu.halt {
-            let cycles = self.cpu.tick(&mut self.mmu).map_err(GameBoyError::Cpu)?;
-            self.clock.add_cycles(cycles);
-        } else {
+        self.step()
+    }
+
+    fn step(&mut self) -> Result<usize, GameBoyError> {
+        if self.cpu.stopped {
+            // Only joypad input leaves STOP mode. Until then the system clock is
+            // stopped, so a frame's worth of time is skipped at once.
+            if !self.mmu.io.joypad.input_asserted() {
+                let stopped_cycles = self.mmu.io.ppu.frame_t_cycles() as usize / 4;
+                self.clock.add_cycles(stopped_cycles);
+
+                let cycles = self.clock.finalize_cycle();
+                self.mmu.advance_stopped(cycles as u64 * 4);
+
+                return Ok(cycles);
+            }
+
+            self.cpu.stopped = false;
             self.clock.add_cycles(1);
+        } else if self.cpu.halt {
+            // A pending interrupt wakes the CPU whether or not IME is set. It's
+            // dispatched on the next cycle if IME is set.
+            if self.mmu.interrupts_scheduled() {
+                self.cpu.halt = false;
+                self.clock.add_cycles(1);
+            } else {
+                let halted_cycles = self.halted_cycles();
+                self.clock.add_cycles(halted_cycles);
+            }
+        } else {
+            let interrupt_cycles = self.cpu.handle_interrupts(&mut self.mmu)?;
+
+            if interrupt_cycles > 0 {
+                self.clock.add_cycles(interrupt_cycles as usize);
+            } else {
+                let cycles = match &mut self.block_cache {
+                    Some(cache) => self.cpu.tick_cached(&mut self.mmu, cache),
+                    None => self.cpu.tick(&mut self.mmu),
+                }
+                .map_err(GameBoyError::Cpu)?;
+                let stall = std::mem::take(&mut self.cpu.speed_switch_stall);
+                self.clock.add_cycles(cycles as usize + stall);
+            }
         }
 
-        if can_handle_this_cycle {
-            let interrupt_cycles = self.cpu.handle_interrupts(&mut self.mmu)?;
-            self.clock.add_cycles(interrupt_cycles);
-        } else if self.cpu.halt && self.mmu.interrupts_scheduled()? {
-            self.cpu.halt = false;
+        let cycles = self.clock.finalize_cycle();
+        self.mmu.advance(cycles as u64 * 4);
+
+        Ok(cycles)
+    }
+
+    /// T-cycles elapsed since power on.
+    pub fn t_cycles(&self) -> u64 {
+        self.mmu.scheduler.now()
+    }
+
+    /// The last frame drawn, one DMG shade from 0 (lightest) to 3 (darkest) per
+    /// pixel, row by row. See `Palette` for turning this into an image.
+    pub fn framebuffer(&self) -> &[u8] {
+        self.mmu.io.ppu.framebuffer()
+    }
+
+    /// Frames that have entered VBlank since power on.
+    pub fn frames(&self) -> u64 {
+        self.mmu.io.ppu.frames()
+    }
+
+    /// The address of the next instruction.
+    pub fn pc(&self) -> u16 {
+        *self.cpu.registers.pc.get_value()
+    }
+
+    pub fn speed_mode(&self) -> SpeedMode {
+        self.mmu.speed_mode()
+    }
+
+    /// Runs stop before executing the instruction at `pc`.
+    pub fn add_breakpoint(&mut self, pc: u16) {
+        if !self.breakpoints.contains(&pc) {
+            self.breakpoints.push(pc);
+        }
+    }
+
+    pub fn remove_breakpoint(&mut self, pc: u16) {
+        self.breakpoints.retain(|&breakpoint| breakpoint != pc);
+    }
+
+    /// Treat `LD B,B` as a breakpoint, the way test ROMs like mooneye's signal
+    /// that they're done. Off by default.
+    pub fn set_software_breakpoints(&mut self, enabled: bool) {
+        self.software_breakpoints = enabled;
+    }
+
+    pub fn registers(&self) -> &Registers {
+        &self.cpu.registers
+    }
+
+    /// Runs until the next frame enters VBlank. With the LCD off or the CPU stopped
+    /// nothing is drawn, so this stops once a frame's worth of t-cycles have elapsed
+    /// instead.
+    pub fn run_frame(&mut self) -> RunResult {
+        if let Some(recorder) = &mut self.recorder {
+            let frame = recorder.next_frame();
+
+            if let Err(e) = self.apply_input(frame) {
+                return RunResult {
+                    t_cycles: 0,
+                    frames: 0,
+                    stop: StopReason::Error(e),
+                };
+            }
         }
 
-        self.handle_peripherals()?;
+        let frames = self.frames();
+        let started = self.t_cycles();
+
+        self.run_with(StopReason::Frame, |gameboy| {
+            let ppu = &gameboy.mmu.io.ppu;
+
+            gameboy.frames() > frames
+                || ((!ppu.enabled() || gameboy.cpu.stopped)
+                    && gameboy.t_cycles() - started >= ppu.frame_t_cycles())
+        })
+    }
+
+    /// Runs for at least `t_cycles` t-cycles. The run stops on an instruction
+    /// boundary, so it may overshoot by part of an instruction or halted stretch.
+    pub fn run_cycles(&mut self, t_cycles: u64) -> RunResult {
+        if let Some(refused) = self.refuse_while_recording() {
+            return refused;
+        }
+
+        let until = self.t_cycles() + t_cycles;
+
+        self.run_with(StopReason::CyclesElapsed, |gameboy| {
+            gameboy.t_cycles() >= until
+        })
+    }
+
+    /// Runs until `condition` holds, checking it after every step.
+    pub fn run_until<F: FnMut(&GameBoy) -> bool>(&mut self, condition: F) -> RunResult {
+        if let Some(refused) = self.refuse_while_recording() {
+            return refused;
+        }
+
+        self.run_with(StopReason::Condition, condition)
+    }
+
+    /// Runs for `duration` of emulated time at the current speed.
+    pub fn run_for(&mut self, duration: Duration) -> RunResult {
+        let oscillation = self.speed_mode().oscillation() as f64;
 
-        Ok(self.clock.finalize_cycle(&mut self.mmu)?)
+        self.run_cycles((duration.as_secs_f64() * oscillation) as u64)
+    }
+
+    fn refuse_while_recording(&self) -> Option<RunResult> {
+        self.recorder.as_ref().map(|_| RunResult {
+            t_cycles: 0,
+            frames: 0,
+            stop: StopReason::Error(GameBoyError::Recording),
+        })
+    }
+
+    fn run_with<F: FnMut(&GameBoy) -> bool>(
+        &mut self,
+        reason: StopReason,
+        mut done: F,
+    ) -> RunResult {
+        let started = self.t_cycles();
+        let frames = self.frames();
+
+        let stop = loop {
+            if let Err(e) = self.step() {
+                break StopReason::Error(e);
+            }
+
+            if done(self) {
+                break reason;
+            }
+
+            let pc = self.pc();
+
+            if self.breakpoints.contains(&pc) {
+                break StopReason::Breakpoint(pc);
+            }
+
+            if self.software_breakpoints && self.mmu.read_byte(pc) == LD_B_B {
+                break StopReason::SoftwareBreakpoint(pc);
+            }
+        };
+
+        RunResult {
+            t_cycles: self.t_cycles() - started,
+            frames: self.frames() - frames,
+            stop,
+        }
     }
 
     pub fn start(&mut self) -> Result<(), GameBoyError> {
+        if self.recorder.is_some() {
+            return Err(GameBoyError::Recording);
+        }
+
         loop {
-            self.cycle()?;
+            self.step()?;
         }
     }
 
-    fn handle_peripherals(&mut self) -> Result<(), GameBoyError> {
-        for p in self.peripherals.iter_mut() {
-            match p {
-                Peripheral::SerialPort(f) => {
-                    let sc = self.mmu.read_byte(0xFF02)?;
+    /// While halted, nothing observable changes until a scheduled event comes due,
+    /// so skip straight to the m-cycle in which the next event fires instead of
+    /// stepping one m-cycle at a time. Devices post `Event::Device` for when they
+    /// next need ticking, so they're covered too.
+    fn halted_cycles(&self) -> usize {
+        if !self.skip_halted {
+            return 1;
+        }
+
+        match self.mmu.scheduler.cycles_until_next_event() {
+            Some(t_cycles) => (t_cycles as usize).div_ceil(4).max(1),
+            None => 1,
+        }
+    }
 
-                    let arg = if sc == 0x81 {
-                        self.mmu.write_byte(0xFF02, 0)?;
-                        Some(self.mmu.read_byte(0xFF01)? as char)
-                    } else {
-                        None
-                    };
+    /// Decode instructions a basic block at a time and reuse them. Emulation is
+    /// exactly the same either way, just faster with the cache. Off by default.
+    pub fn set_block_cache(&mut self, enabled: bool) {
+        self.block_cache = enabled.then(BlockCache::default);
+    }
 
-                    f(arg)
-                }
+    /// Turn accesses to unmapped memory into errors instead of open bus reads and
+    /// ignored writes. Useful for catching emulator bugs, off by default.
+    pub fn set_strict_memory(&mut self, strict: bool) {
+        self.mmu.set_strict(strict);
+    }
+
+    pub fn set_button(&mut self, button: Button, pressed: bool) -> Result<(), GameBoyError> {
+        match &mut self.recorder {
+            Some(recorder) => {
+                recorder.set_button(button, pressed);
+                Ok(())
+            }
+            None => {
+                self.mmu.set_button(button, pressed);
+                Ok(())
             }
         }
+    }
+
+    /// Restarts the console, leaving the cartridge's RAM and banks as they are.
+    /// Attached devices and the link cable stay plugged in and held buttons stay
+    /// held.
+    pub fn reset(&mut self) -> Result<(), GameBoyError> {
+        self.request_restart(Restart::Reset)
+    }
+
+    /// Switches off and on again. Everything starts afresh, cartridge included,
+    /// as if this were a new `GameBoy` with the same devices and link cable.
+    pub fn power_cycle(&mut self) -> Result<(), GameBoyError> {
+        self.request_restart(Restart::Power)
+    }
+
+    /// Records input into a `Movie` from now on, starting with a power cycle so
+    /// that it plays back from power on.
+    ///
+    /// While recording, buttons, resets and power cycles take effect at the start
+    /// of the next `run_frame`, applied the same way `Player` applies them, so
+    /// playback is exact. Buttons pressed and released between two frames are
+    /// never seen. Since input only lands on frames, `cycle`, `run_cycles`,
+    /// `run_until`, `run_for` and `start` refuse to run with
+    /// `GameBoyError::Recording` until recording stops.
+    pub fn start_recording(&mut self) {
+        let rom = &self.mmu.rom;
+        self.recorder = Some(Recorder::new(rom, self.held_buttons()));
+    }
+
+    /// The movie recorded since `start_recording`, if recording.
+    pub fn stop_recording(&mut self) -> Option<Movie> {
+        self.recorder.take().map(Recorder::finish)
+    }
+
+    /// Restarts if the frame says to, then sets every button in the same order,
+    /// so that recording and playback request the same interrupts.
+    pub(crate) fn apply_input(&mut self, frame: Frame) -> Result<(), GameBoyError> {
+        if let Some(restart) = frame.restart {
+            self.restart(restart)?;
+        }
+
+        for button in Button::ALL {
+            self.mmu.set_button(button, frame.is_held(button));
+        }
+
+        Ok(())
+    }
+
+    fn held_buttons(&self) -> Frame {
+        let mut frame = Frame::default();
+
+        for button in Button::ALL {
+            frame.set_held(button, self.mmu.io.joypad.is_pressed(button));
+        }
+
+        frame
+    }
+
+    fn request_restart(&mut self, restart: Restart) -> Result<(), GameBoyError> {
+        match &mut self.recorder {
+            Some(recorder) => {
+                recorder.restart(restart);
+                Ok(())
+            }
+            None => self.restart(restart),
+        }
+    }
+
+    fn restart(&mut self, restart: Restart) -> Result<(), GameBoyError> {
+        self.cpu = CPU::new()?;
+        self.clock = Clock::default();
+        self.mmu
+            .restart(&self.cartridge, restart == Restart::Reset)?;
+
+        // The MMU's write counts start again, so they can't vouch for old blocks
+        if let Some(cache) = &mut self.block_cache {
+            *cache = BlockCache::default();
+        }
 
         Ok(())
     }
 
-    pub fn attach_peripheral(&mut self, p: Peripheral<'a>) {
-        self.peripherals.push(p);
+    /// Attaches `cable` as the other end of the serial port, replacing the cable
+    /// connected before.
+    pub fn connect_link_cable(&mut self, cable: Box<dyn Device>) {
+        self.mmu.devices.connect_link(cable);
+        self.mmu.schedule_devices();
+
+        if let Some(cache) = &mut self.block_cache {
+            *cache = BlockCache::default();
+        }
+    }
+
+    pub fn attach_device(&mut self, device: Box<dyn Device>) {
+        self.mmu.devices.attach(device);
+        self.mmu.schedule_devices();
+
+        // Code under the device's addresses can't be cached any more
+        if let Some(cache) = &mut self.block_cache {
+            *cache = BlockCache::default();
+        }
+    }
+
+    /// A hash of the CPU, memory, IO and mapper state. Two runs of the same ROM
+    /// with the same inputs should hash the same after every cycle, so a change
+    /// here means emulation diverged. It's stable across platforms, but changes
+    /// whenever the emulated state gains a field.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = Fnv1a::default();
+
+        self.cpu.hash(&mut hasher);
+        self.clock.hash(&mut hasher);
+        self.mmu.hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    /// The state of each attached device, in the order they were attached.
+    pub fn device_states(&self) -> Vec<Vec<u8>> {
+        self.mmu.devices.save_state()
+    }
+
+    pub fn load_device_states(&mut self, states: &[Vec<u8>]) {
+        self.mmu.devices.load_state(states);
+        self.mmu.schedule_devices();
+    }
+}
+
+#[cfg(test)]
+mod gameboy_test {
+    use crate::device::{Device, Tick};
+    use crate::link::capture::ByteCapture;
+    use crate::spec::gameboy::{GameBoy, StopReason};
+    use crate::spec::hardware_registers::Interrupt;
+    use crate::spec::joypad::Button;
+    use crate::spec::register::TRegister;
+    use crate::test_support::rom_with_program;
+    use std::time::Duration;
+
+    fn run_until_pc(gameboy: &mut GameBoy, pc: u16) {
+        for _ in 0..1000 {
+            if *gameboy.cpu.registers.pc.get_value() == pc {
+                return;
+            }
+
+            gameboy.cycle().unwrap();
+        }
+
+        panic!("PC never reached {:X}", pc);
+    }
+
+    fn stack_top(gameboy: &GameBoy) -> u16 {
+        gameboy.mmu.read_word(*gameboy.cpu.registers.sp.get_value())
+    }
+
+    /// Turns the LCD off, enables and requests the timer interrupt, leaving A = 4.
+    const REQUEST_TIMER_INTERRUPT: [u8; 9] = [
+        0xAF, // XOR A
+        0xE0, 0x40, // LDH (LCDC), A
+        0x3E, 0x04, // LD A, 0x04
+        0xE0, 0xFF, // LDH (IE), A
+        0xE0, 0x0F, // LDH (IF), A
+    ];
+
+    #[test]
+    fn halt_fast_forwards_to_the_next_interrupt() {
+        let rom = rom_with_program(&[
+            0xAF, // XOR A
+            0xE0, 0x40, // LDH (LCDC), A
+            0x3E, 0x05, // LD A, 0x05
+            0xE0, 0x07, // LDH (TAC), A
+            0x3E, 0x04, // LD A, 0x04
+            0xE0, 0xFF, // LDH (IE), A
+            0x76, // HALT
+            0x00, // NOP
+        ]);
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        while !gameboy.cpu.halt {
+            gameboy.cycle().unwrap();
+        }
+
+        let overflow_at = gameboy.mmu.scheduler.next_event_at().unwrap();
+        let mut iterations = 0;
+
+        while gameboy.cpu.halt {
+            gameboy.cycle().unwrap();
+            iterations += 1;
+        }
+
+        // Stepping one m-cycle at a time observes the overflow on the first m-cycle
+        // boundary at or after it fires, and wakes on the m-cycle after that.
+        assert_eq!(gameboy.mmu.scheduler.now(), overflow_at.div_ceil(4) * 4 + 4);
+        assert_eq!(iterations, 2);
+    }
+
+    /// Requests the joypad interrupt every `period` t-cycles.
+    struct Metronome {
+        period: u64,
+        next: u64,
+    }
+
+    impl Device for Metronome {
+        fn tick(&mut self, tick: &Tick) -> u8 {
+            if tick.now >= self.next {
+                self.next += self.period;
+                Interrupt::Joypad.get_position()
+            } else {
+                0
+            }
+        }
+
+        fn next_tick(&self, _now: u64) -> Option<u64> {
+            Some(self.next)
+        }
+    }
+
+    /// The t-cycle and state hash after every step taken while not halted.
+    fn awake_states(skip_halted: bool) -> (Vec<(u64, u64)>, Vec<u8>) {
+        let mut rom = rom_with_program(&[
+            0x3E, 0x05, // LD A, 0x05
+            0xE0, 0x07, // LDH (TAC), A
+            0x3E, 0x1C, // LD A, 0x1C
+            0xE0, 0xFF, // LDH (IE), A
+            0x3E, 0x81, // LD A, 0x81
+            0xE0, 0x02, // LDH (SC), A
+            0xFB, // EI
+            0x76, // HALT
+            0x00, // NOP
+            0x04, // INC B
+            0x18, 0xFB, // JR -5
+        ]);
+        // Timer: INC C, RETI
+        rom[0x50..0x52].copy_from_slice(&[0x0C, 0xD9]);
+        // Serial: LD A, 0x81, LDH (SC), A, INC D, RETI
+        rom[0x58..0x5E].copy_from_slice(&[0x3E, 0x81, 0xE0, 0x02, 0x14, 0xD9]);
+        // Joypad: INC E, RETI
+        rom[0x60..0x62].copy_from_slice(&[0x1C, 0xD9]);
+
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+        let capture = ByteCapture::default();
+
+        gameboy.skip_halted = skip_halted;
+        gameboy.connect_link_cable(Box::new(capture.clone()));
+        gameboy.attach_device(Box::new(Metronome {
+            period: 1001,
+            next: 1001,
+        }));
+
+        let mut states = vec![];
+
+        while gameboy.t_cycles() < 200_000 {
+            gameboy.cycle().unwrap();
+
+            if !gameboy.cpu.halt {
+                states.push((gameboy.t_cycles(), gameboy.state_hash()));
+            }
+        }
+
+        (states, capture.bytes())
+    }
+
+    #[test]
+    fn skipping_ahead_while_halted_changes_nothing() {
+        let (skipped, skipped_sent) = awake_states(true);
+        let (stepped, stepped_sent) = awake_states(false);
+
+        assert_eq!(skipped, stepped);
+        assert_eq!(skipped_sent, stepped_sent);
+        assert!(skipped_sent.len() > 10);
+    }
+
+    #[test]
+    fn halt_bug_executes_the_next_byte_twice() {
+        let mut program = REQUEST_TIMER_INTERRUPT.to_vec();
+        program.extend_from_slice(&[
+            0x76, // HALT
+            0x3C, // INC A
+            0x00, // NOP
+        ]);
+        let rom = rom_with_program(&program);
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        run_until_pc(&mut gameboy, 0x10B);
+
+        assert!(!gameboy.cpu.halt);
+        assert_eq!(*gameboy.cpu.registers.a.get_value(), 6);
+    }
+
+    #[test]
+    fn ei_then_halt_bug_returns_to_the_halt() {
+        let mut program = REQUEST_TIMER_INTERRUPT.to_vec();
+        program.extend_from_slice(&[
+            0xFB, // EI
+            0x76, // HALT
+            0x00, // NOP
+        ]);
+        let rom = rom_with_program(&program);
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        run_until_pc(&mut gameboy, 0x50);
+
+        assert_eq!(stack_top(&gameboy), 0x10A);
+    }
+
+    #[test]
+    fn ei_then_halt_bug_at_the_top_of_memory_returns_to_the_halt() {
+        let rom = rom_with_program(&[
+            0xAF, // XOR A
+            0xE0, 0x40, // LDH (LCDC), A
+            0x3E, 0x04, // LD A, 0x04
+            0xE0, 0x0F, // LDH (IF), A
+            0x3E, 0xFB, // LD A, 0xFB (EI)
+            0xE0, 0xFE, // LDH (0xFE), A
+            0x3E, 0x76, // LD A, 0x76 (HALT), enabling the timer interrupt
+            0xE0, 0xFF, // LDH (IE), A
+            0xC3, 0xFE, 0xFF, // JP 0xFFFE
+        ]);
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        run_until_pc(&mut gameboy, 0x50);
+
+        assert_eq!(stack_top(&gameboy), 0xFFFF);
+    }
+
+    #[test]
+    fn ei_takes_effect_after_the_next_instruction() {
+        let mut program = REQUEST_TIMER_INTERRUPT.to_vec();
+        program.extend_from_slice(&[
+            0xFB, // EI
+            0x3C, // INC A
+            0x3C, // INC A
+        ]);
+        let rom = rom_with_program(&program);
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        run_until_pc(&mut gameboy, 0x50);
+
+        assert_eq!(stack_top(&gameboy), 0x10B);
+        assert_eq!(*gameboy.cpu.registers.a.get_value(), 5);
+    }
+
+    #[test]
+    fn ei_then_di_does_not_dispatch() {
+        let mut program = REQUEST_TIMER_INTERRUPT.to_vec();
+        program.extend_from_slice(&[
+            0xFB, // EI
+            0xF3, // DI
+            0x00, // NOP
+        ]);
+        let rom = rom_with_program(&program);
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        run_until_pc(&mut gameboy, 0x10C);
+
+        assert!(!gameboy.cpu.ime);
+    }
+
+    #[test]
+    fn state_hash_follows_emulation() {
+        let rom = rom_with_program(&[
+            0x3C, // INC A
+            0x18, 0xFD, // JR -3
+        ]);
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+        let mut other = GameBoy::new(&rom).unwrap();
+
+        assert_eq!(gameboy.state_hash(), other.state_hash());
+
+        gameboy.run_cycles(1000);
+        assert_ne!(gameboy.state_hash(), other.state_hash());
+
+        other.run_cycles(1000);
+        assert_eq!(gameboy.state_hash(), other.state_hash());
+
+        gameboy.set_button(Button::A, true).unwrap();
+        assert_ne!(gameboy.state_hash(), other.state_hash());
+    }
+
+    #[test]
+    fn stop_waits_for_joypad_input() {
+        let rom = rom_with_program(&[
+            0x3E, 0x10, // LD A, 0x10
+            0xE0, 0x00, // LDH (P1), A
+            0x10, 0x00, // STOP
+            0x3C, // INC A
+            0x00, // NOP
+        ]);
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        run_until_pc(&mut gameboy, 0x106);
+        assert!(gameboy.cpu.stopped);
+        assert_eq!(gameboy.mmu.read_byte(0xFF04), 0);
+
+        for _ in 0..100 {
+            gameboy.cycle().unwrap();
+        }
+        assert!(gameboy.cpu.stopped);
+
+        gameboy.set_button(Button::Start, true).unwrap();
+        run_until_pc(&mut gameboy, 0x107);
+
+        assert!(!gameboy.cpu.stopped);
+        assert_eq!(*gameboy.cpu.registers.a.get_value(), 0x11);
+    }
+
+    #[test]
+    fn stop_freezes_the_timer_and_ppu() {
+        let rom = rom_with_program(&[
+            0x3E, 0x05, // LD A, 0x05
+            0xE0, 0x07, // LDH (TAC), A
+            0x3E, 0x10, // LD A, 0x10
+            0xE0, 0x00, // LDH (P1), A
+            0x10, 0x00, // STOP
+            0x00, // NOP
+        ]);
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        run_until_pc(&mut gameboy, 0x10A);
+        assert!(gameboy.cpu.stopped);
+
+        let started = gameboy.t_cycles();
+        let tima = gameboy.mmu.read_byte(0xFF05);
+        let ly = gameboy.mmu.read_byte(0xFF44);
+        let interrupt_flag = gameboy.mmu.read_byte(0xFF0F);
+
+        for _ in 0..10 {
+            gameboy.cycle().unwrap();
+        }
+
+        assert!(gameboy.cpu.stopped);
+        assert!(gameboy.t_cycles() - started >= 10 * gameboy.mmu.io.ppu.frame_t_cycles());
+        assert_eq!(gameboy.mmu.read_byte(0xFF04), 0);
+        assert_eq!(gameboy.mmu.read_byte(0xFF05), tima);
+        assert_eq!(gameboy.mmu.read_byte(0xFF44), ly);
+        assert_eq!(gameboy.mmu.read_byte(0xFF0F), interrupt_flag);
+
+        gameboy.set_button(Button::Start, true).unwrap();
+        run_until_pc(&mut gameboy, 0x10B);
+        assert!(!gameboy.cpu.stopped);
+    }
+
+    #[test]
+    fn stop_with_a_button_held_halts_instead() {
+        let rom = rom_with_program(&[
+            0x3E, 0x10, // LD A, 0x10
+            0xE0, 0x00, // LDH (P1), A
+            0x10, 0x00, // STOP
+            0x00, // NOP
+        ]);
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        gameboy.set_button(Button::A, true).unwrap();
+        run_until_pc(&mut gameboy, 0x106);
+
+        assert!(gameboy.cpu.halt);
+        assert!(!gameboy.cpu.stopped);
+        assert_ne!(gameboy.mmu.read_byte(0xFF04), 0);
+    }
+
+    #[test]
+    fn stop_switches_speed_when_key1_is_armed() {
+        let mut rom = rom_with_program(&[
+            0x3E, 0x01, // LD A, 0x01
+            0xE0, 0x4D, // LDH (KEY1), A
+            0x10, 0x00, // STOP
+            0xF0, 0x4D, // LDH A, (KEY1)
+            0x00, // NOP
+        ]);
+        rom[0x143] = 0x80;
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        run_until_pc(&mut gameboy, 0x108);
+
+        assert!(!gameboy.cpu.stopped);
+        assert_eq!(*gameboy.cpu.registers.a.get_value(), 0xFE);
+    }
+
+    #[test]
+    fn key1_is_not_present_on_dmg_cartridges() {
+        let rom = rom_with_program(&[
+            0x3E, 0x01, // LD A, 0x01
+            0xE0, 0x4D, // LDH (KEY1), A
+            0x10, 0x00, // STOP
+            0x00, // NOP
+        ]);
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        run_until_pc(&mut gameboy, 0x106);
+
+        assert!(gameboy.cpu.stopped);
+        assert_eq!(gameboy.mmu.read_byte(0xFF4D), 0xFF);
+    }
+
+    #[test]
+    fn run_frame_stops_at_vblank() {
+        let rom = rom_with_program(&[0x18, 0xFE]); // JR -2
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        let result = gameboy.run_frame();
+
+        assert!(matches!(result.stop, StopReason::Frame));
+        assert_eq!(result.frames, 1);
+        assert_eq!(gameboy.mmu.io.ppu.ly(), 144);
+
+        let result = gameboy.run_frame();
+
+        assert_eq!(result.frames, 1);
+        assert!((70224..70224 + 12).contains(&result.t_cycles));
+    }
+
+    #[test]
+    fn run_frame_with_the_lcd_off_runs_a_frame_of_cycles() {
+        let rom = rom_with_program(&[
+            0xAF, // XOR A
+            0xE0, 0x40, // LDH (LCDC), A
+            0x18, 0xFE, // JR -2
+        ]);
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        let result = gameboy.run_frame();
+
+        assert!(matches!(result.stop, StopReason::Frame));
+        assert_eq!(result.frames, 0);
+        assert!(result.t_cycles >= 70224);
+    }
+
+    #[test]
+    fn run_cycles_and_run_for_stop_once_time_has_elapsed() {
+        let rom = rom_with_program(&[0x18, 0xFE]); // JR -2
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        let result = gameboy.run_cycles(1000);
+
+        assert!(matches!(result.stop, StopReason::CyclesElapsed));
+        assert!((1000..1012).contains(&result.t_cycles));
+
+        let result = gameboy.run_for(Duration::from_millis(100));
+
+        assert!((419430..419442).contains(&result.t_cycles));
+        assert_eq!(result.frames, 6);
+    }
+
+    #[test]
+    fn runs_stop_at_breakpoints_and_conditions() {
+        let rom = rom_with_program(&[
+            0x3C, // INC A
+            0x3C, // INC A
+            0x18, 0xFC, // JR -4
+        ]);
+        let mut gameboy = GameBoy::new(&rom).unwrap();
+
+        gameboy.add_breakpoint(0x102);
+        let result = gameboy.run_frame();
+
+        assert!(matches!(result.stop, StopReason::Breakpoint(0x102)));
+        assert_eq!(gameboy.pc(), 0x102);
+
+        gameboy.remove_breakpoint(0x102);
+        let result = gameboy.run_until(|gameboy| *gameboy.cpu.registers.a.get_value() == 0x10);
+
+        assert!(matches!(result.stop, StopReason::Condition));
+        assert_eq!(*gameboy.cpu.registers.a.get_value(), 0x10);
     }
 }
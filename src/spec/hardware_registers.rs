@@ -1,20 +1,172 @@
-use crate::spec::memory_region::MemoryRegion;
+use crate::spec::apu::Apu;
+use crate::spec::cgb::CgbRegisters;
+use crate::spec::joypad::Joypad;
+use crate::spec::mmu::OPEN_BUS;
+use crate::spec::ppu::Ppu;
+use crate::spec::scheduler::Scheduler;
+use crate::spec::serial::Serial;
+use crate::spec::timer::Timer;
 use std::convert::TryFrom;
 
-pub struct HardwareRegister {
-    registers: [u8; 0x7F],
+pub mod io_address {
+    pub const P1: u16 = 0xFF00;
+    pub const SB: u16 = 0xFF01;
+    pub const SC: u16 = 0xFF02;
+    pub const DIV: u16 = 0xFF04;
+    pub const TIMA: u16 = 0xFF05;
+    pub const TMA: u16 = 0xFF06;
+    pub const TAC: u16 = 0xFF07;
+    pub const IF: u16 = 0xFF0F;
+    pub const NR10: u16 = 0xFF10;
+    pub const NR11: u16 = 0xFF11;
+    pub const NR12: u16 = 0xFF12;
+    pub const NR13: u16 = 0xFF13;
+    pub const NR14: u16 = 0xFF14;
+    pub const NR21: u16 = 0xFF16;
+    pub const NR22: u16 = 0xFF17;
+    pub const NR23: u16 = 0xFF18;
+    pub const NR24: u16 = 0xFF19;
+    pub const NR30: u16 = 0xFF1A;
+    pub const NR31: u16 = 0xFF1B;
+    pub const NR32: u16 = 0xFF1C;
+    pub const NR33: u16 = 0xFF1D;
+    pub const NR34: u16 = 0xFF1E;
+    pub const NR41: u16 = 0xFF20;
+    pub const NR42: u16 = 0xFF21;
+    pub const NR43: u16 = 0xFF22;
+    pub const NR44: u16 = 0xFF23;
+    pub const NR50: u16 = 0xFF24;
+    pub const NR51: u16 = 0xFF25;
+    pub const NR52: u16 = 0xFF26;
+    pub const WAVE_RAM_START: u16 = 0xFF30;
+    pub const WAVE_RAM_END: u16 = 0xFF3F;
+    pub const LCDC: u16 = 0xFF40;
+    pub const STAT: u16 = 0xFF41;
+    pub const SCY: u16 = 0xFF42;
+    pub const SCX: u16 = 0xFF43;
+    pub const LY: u16 = 0xFF44;
+    pub const LYC: u16 = 0xFF45;
+    pub const DMA: u16 = 0xFF46;
+    pub const BGP: u16 = 0xFF47;
+    pub const OBP0: u16 = 0xFF48;
+    pub const OBP1: u16 = 0xFF49;
+    pub const WY: u16 = 0xFF4A;
+    pub const WX: u16 = 0xFF4B;
+    pub const KEY1: u16 = 0xFF4D;
+    pub const VBK: u16 = 0xFF4F;
+    pub const BOOT: u16 = 0xFF50;
+    pub const HDMA1: u16 = 0xFF51;
+    pub const HDMA2: u16 = 0xFF52;
+    pub const HDMA3: u16 = 0xFF53;
+    pub const HDMA4: u16 = 0xFF54;
+    pub const HDMA5: u16 = 0xFF55;
+    pub const RP: u16 = 0xFF56;
+    pub const BCPS: u16 = 0xFF68;
+    pub const BCPD: u16 = 0xFF69;
+    pub const OCPS: u16 = 0xFF6A;
+    pub const OCPD: u16 = 0xFF6B;
+    pub const OPRI: u16 = 0xFF6C;
+    pub const SVBK: u16 = 0xFF70;
+    pub const IE: u16 = 0xFFFF;
 }
 
-impl Default for HardwareRegister {
-    fn default() -> Self {
-        HardwareRegister {
-            registers: [0; 0x7F],
-        }
+use io_address::*;
+
+/// Only the low five bits of IE and IF correspond to interrupts.
+const INTERRUPT_MASK: u8 = 0x1F;
+
+/// Which bits of an IO register are wired up.
+///
+/// Bits set in `read` always read back as 1, either because they're unused or
+/// because the register is write only. Bits clear in `write` ignore writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterMask {
+    pub read: u8,
+    pub write: u8,
+}
+
+impl RegisterMask {
+    /// Nothing is connected at this address.
+    pub const UNMAPPED: RegisterMask = RegisterMask::new(0xFF, 0);
+
+    const fn new(read: u8, write: u8) -> Self {
+        RegisterMask { read, write }
+    }
+
+    /// A register where every connected bit can be read and written.
+    const fn read_write(unused: u8) -> Self {
+        RegisterMask::new(unused, !unused)
+    }
+
+    /// Applies a write to the current value of a register.
+    pub fn apply(&self, current: u8, value: u8) -> u8 {
+        (current & !self.write) | (value & self.write)
     }
 }
 
-#[derive(Debug)]
-pub enum HardwareRegisterError {}
+/// Read and write masks for the IO registers, following the pandocs. The CGB only
+/// registers read as open bus on a DMG whatever their mask.
+pub fn register_mask(address: u16) -> RegisterMask {
+    match address {
+        P1 => RegisterMask::new(0xC0, 0x30),
+        SB => RegisterMask::read_write(0),
+        SC => RegisterMask::read_write(0x7E),
+        DIV..=TMA => RegisterMask::read_write(0),
+        TAC => RegisterMask::read_write(0xF8),
+        IF => RegisterMask::read_write(0xE0),
+        NR10 => RegisterMask::read_write(0x80),
+        NR11 => RegisterMask::new(0x3F, 0xFF),
+        NR12 => RegisterMask::read_write(0),
+        NR13 => RegisterMask::new(0xFF, 0xFF),
+        NR14 => RegisterMask::new(0xBF, 0xC7),
+        NR21 => RegisterMask::new(0x3F, 0xFF),
+        NR22 => RegisterMask::read_write(0),
+        NR23 => RegisterMask::new(0xFF, 0xFF),
+        NR24 => RegisterMask::new(0xBF, 0xC7),
+        NR30 => RegisterMask::read_write(0x7F),
+        NR31 => RegisterMask::new(0xFF, 0xFF),
+        NR32 => RegisterMask::read_write(0x9F),
+        NR33 => RegisterMask::new(0xFF, 0xFF),
+        NR34 => RegisterMask::new(0xBF, 0xC7),
+        NR41 => RegisterMask::new(0xFF, 0x3F),
+        NR42 | NR43 => RegisterMask::read_write(0),
+        NR44 => RegisterMask::new(0xBF, 0xC0),
+        NR50 | NR51 => RegisterMask::read_write(0),
+        // The channel status bits of NR52 are read only
+        NR52 => RegisterMask::new(0x70, 0x80),
+        WAVE_RAM_START..=WAVE_RAM_END => RegisterMask::read_write(0),
+        LCDC => RegisterMask::read_write(0),
+        STAT => RegisterMask::new(0x80, 0x78),
+        SCY | SCX => RegisterMask::read_write(0),
+        LY => RegisterMask::new(0, 0),
+        LYC..=WX => RegisterMask::read_write(0),
+        KEY1 => RegisterMask::new(0x7E, 0x01),
+        VBK => RegisterMask::read_write(0xFE),
+        // HDMA1-4 are write only, and HDMA5 reads as no transfer active
+        HDMA1 | HDMA5 => RegisterMask::new(0xFF, 0xFF),
+        HDMA2 | HDMA4 => RegisterMask::new(0xFF, 0xF0),
+        HDMA3 => RegisterMask::new(0xFF, 0x1F),
+        // Bit 1 is the received signal, which reads 1 with nothing to receive
+        RP => RegisterMask::new(0x3E, 0xC1),
+        BCPS | OCPS => RegisterMask::read_write(0x40),
+        BCPD | OCPD => RegisterMask::read_write(0),
+        OPRI => RegisterMask::read_write(0xFE),
+        SVBK => RegisterMask::read_write(0xF8),
+        IE => RegisterMask::read_write(0),
+        _ => RegisterMask::UNMAPPED,
+    }
+}
+
+/// A component that owns some of the IO registers.
+///
+/// Reads and writes only see the connected bits of a register. `IoRegisters`
+/// applies the masks from `register_mask` on the way in and out.
+pub trait IoHandler {
+    fn read_register(&self, address: u16, scheduler: &Scheduler) -> u8;
+
+    /// Returns the interrupt bits requested by the write.
+    fn write_register(&mut self, address: u16, value: u8, scheduler: &mut Scheduler) -> u8;
+}
 
 #[derive(Debug)]
 pub enum Interrupt {
@@ -64,29 +216,288 @@ impl Interrupt {
     }
 }
 
-impl MemoryRegion for HardwareRegister {
-    type Error = HardwareRegisterError;
+/// IE and IF.
+#[derive(Default, Hash)]
+pub struct InterruptRegisters {
+    enable: u8,
+    flag: u8,
+}
+
+impl InterruptRegisters {
+    /// Interrupts that are both requested and enabled, regardless of IME.
+    pub fn pending(&self) -> u8 {
+        self.enable & self.flag & INTERRUPT_MASK
+    }
+
+    pub fn request(&mut self, interrupts: u8) {
+        self.flag |= interrupts & INTERRUPT_MASK;
+    }
+
+    pub fn acknowledge(&mut self, interrupt: &Interrupt) {
+        self.flag &= !interrupt.get_position();
+    }
+}
+
+impl IoHandler for InterruptRegisters {
+    fn read_register(&self, address: u16, _scheduler: &Scheduler) -> u8 {
+        match address {
+            IF => self.flag,
+            IE => self.enable,
+            _ => unreachable!("Interrupts do not own address {:X}", address),
+        }
+    }
 
-    fn map_read(&self, address: u16) -> Result<u8, Self::Error> {
-        // GBDEBUG, todo: add flag here
-        if address == 0xFF44 {
-            return Ok(0x90);
+    fn write_register(&mut self, address: u16, value: u8, _scheduler: &mut Scheduler) -> u8 {
+        match address {
+            IF => self.flag = value,
+            IE => self.enable = value,
+            _ => unreachable!("Interrupts do not own address {:X}", address),
         }
-        Ok(self.registers[(address - 0xFF00) as usize])
+
+        0
     }
+}
 
-    fn map_write(&mut self, address: u16, value: u8) -> Result<(), Self::Error> {
-        let offset = (address - 0xFF00) as usize;
+/// The component that an IO register belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Owner {
+    Joypad,
+    Serial,
+    Timer,
+    Interrupts,
+    Ppu,
+    Apu,
+    Cgb,
+    Unmapped,
+}
+
+impl Owner {
+    pub fn of(address: u16) -> Owner {
         match address {
-            0xFF04 => {
-                self.registers[offset] = 0;
-                Ok(())
-            }
-            _ => {
-                self.registers[(address - 0xFF00) as usize] = value;
+            P1 => Owner::Joypad,
+            SB | SC => Owner::Serial,
+            DIV..=TAC => Owner::Timer,
+            IF | IE => Owner::Interrupts,
+            NR10..=WAVE_RAM_END if register_mask(address) != RegisterMask::UNMAPPED => Owner::Apu,
+            LCDC..=LYC | BGP..=WX => Owner::Ppu,
+            KEY1 | VBK | HDMA1..=RP | BCPS..=OPRI | SVBK => Owner::Cgb,
+            _ => Owner::Unmapped,
+        }
+    }
+}
+
+/// The IO registers at 0xFF00-0xFF7F and IE, each routed to the component that
+/// owns it.
+///
+/// OAM DMA at 0xFF46 needs access to the whole bus, so the MMU handles it
+/// before anything gets here.
+#[derive(Default, Hash)]
+pub struct IoRegisters {
+    pub joypad: Joypad,
+    pub serial: Serial,
+    pub timer: Timer,
+    pub interrupts: InterruptRegisters,
+    pub ppu: Ppu,
+    pub apu: Apu,
+    pub cgb: CgbRegisters,
+}
 
-                Ok(())
+impl IoRegisters {
+    pub fn read(&self, address: u16, scheduler: &Scheduler) -> u8 {
+        let value = match self.handler(address) {
+            Some(handler) => handler.read_register(address, scheduler),
+            None => OPEN_BUS,
+        };
+
+        value | register_mask(address).read
+    }
+
+    /// Any interrupts requested by the owning component are raised in IF.
+    pub fn write(&mut self, address: u16, value: u8, scheduler: &mut Scheduler) {
+        let mask = register_mask(address);
+
+        let interrupts = match self.handler_mut(address) {
+            Some(handler) => {
+                let current = handler.read_register(address, scheduler);
+                handler.write_register(address, mask.apply(current, value), scheduler)
             }
+            None => 0,
+        };
+
+        self.interrupts.request(interrupts);
+    }
+
+    fn handler(&self, address: u16) -> Option<&dyn IoHandler> {
+        match Owner::of(address) {
+            Owner::Joypad => Some(&self.joypad),
+            Owner::Serial => Some(&self.serial),
+            Owner::Timer => Some(&self.timer),
+            Owner::Interrupts => Some(&self.interrupts),
+            Owner::Ppu => Some(&self.ppu),
+            Owner::Apu => Some(&self.apu),
+            Owner::Cgb => Some(&self.cgb),
+            Owner::Unmapped => None,
+        }
+    }
+
+    fn handler_mut(&mut self, address: u16) -> Option<&mut dyn IoHandler> {
+        match Owner::of(address) {
+            Owner::Joypad => Some(&mut self.joypad),
+            Owner::Serial => Some(&mut self.serial),
+            Owner::Timer => Some(&mut self.timer),
+            Owner::Interrupts => Some(&mut self.interrupts),
+            Owner::Ppu => Some(&mut self.ppu),
+            Owner::Apu => Some(&mut self.apu),
+            Owner::Cgb => Some(&mut self.cgb),
+            Owner::Unmapped => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod hardware_registers_test {
+    use crate::spec::cartridge_header::Cartridge;
+    use crate::spec::hardware_registers::io_address::*;
+    use crate::spec::hardware_registers::{register_mask, Owner, RegisterMask};
+    use crate::spec::mmu::MMU;
+
+    /// (address, read after writing 0x00, read after writing 0xFF) on a DMG
+    const DOCUMENTED_READS: [(u16, u8, u8); 23] = [
+        (0xFEA0, 0xFF, 0xFF),
+        (SB, 0x00, 0xFF),
+        (SC, 0x7E, 0xFF),
+        (0xFF03, 0xFF, 0xFF),
+        (TAC, 0xF8, 0xFF),
+        (IF, 0xE0, 0xFF),
+        (NR10, 0x80, 0xFF),
+        (NR11, 0x3F, 0xFF),
+        (NR13, 0xFF, 0xFF),
+        (NR14, 0xBF, 0xFF),
+        (0xFF15, 0xFF, 0xFF),
+        (NR30, 0x7F, 0xFF),
+        (NR32, 0x9F, 0xFF),
+        (NR41, 0xFF, 0xFF),
+        (NR44, 0xBF, 0xFF),
+        (NR52, 0x70, 0xF0),
+        (0xFF27, 0xFF, 0xFF),
+        (WAVE_RAM_START, 0x00, 0xFF),
+        // The LCD is on with LY = LYC = 0 during OAM scan
+        (STAT, 0x86, 0xFE),
+        (0xFF4C, 0xFF, 0xFF),
+        (KEY1, 0xFF, 0xFF),
+        (BOOT, 0xFF, 0xFF),
+        (0xFF7F, 0xFF, 0xFF),
+    ];
+
+    /// (address, read after writing 0x00, read after writing 0xFF) on a CGB. The
+    /// palette data registers are left to `palette_data_is_indexed_and_auto_increments`.
+    const CGB_READS: [(u16, u8, u8); 12] = [
+        (VBK, 0xFE, 0xFF),
+        (HDMA1, 0xFF, 0xFF),
+        (HDMA2, 0xFF, 0xFF),
+        (HDMA3, 0xFF, 0xFF),
+        (HDMA4, 0xFF, 0xFF),
+        (HDMA5, 0xFF, 0xFF),
+        (RP, 0x3E, 0xFF),
+        (BCPS, 0x40, 0xFF),
+        (OCPS, 0x40, 0xFF),
+        (OPRI, 0xFE, 0xFF),
+        (SVBK, 0xF8, 0xFF),
+        (KEY1, 0x7E, 0x7F),
+    ];
+
+    #[test]
+    fn registers_read_back_documented_values() {
+        let rom = vec![0; 0x8000];
+        let cartridge = Cartridge::new(&rom).unwrap();
+        let mut mmu = MMU::new(&rom, &cartridge).unwrap();
+
+        for (address, low, high) in DOCUMENTED_READS {
+            mmu.write_byte(address, 0x00);
+            assert_eq!(mmu.read_byte(address), low, "{:X} <- 0x00", address);
+
+            mmu.write_byte(address, 0xFF);
+            assert_eq!(mmu.read_byte(address), high, "{:X} <- 0xFF", address);
+        }
+    }
+
+    #[test]
+    fn unmapped_registers_ignore_writes() {
+        assert_eq!(register_mask(0xFF03), RegisterMask::UNMAPPED);
+        assert_eq!(register_mask(LY).write, 0);
+        assert_eq!(register_mask(NR52).write, 0x80);
+    }
+
+    #[test]
+    fn registers_are_routed_to_their_owner() {
+        assert_eq!(Owner::of(P1), Owner::Joypad);
+        assert_eq!(Owner::of(SC), Owner::Serial);
+        assert_eq!(Owner::of(DIV), Owner::Timer);
+        assert_eq!(Owner::of(IE), Owner::Interrupts);
+        assert_eq!(Owner::of(NR52), Owner::Apu);
+        assert_eq!(Owner::of(0xFF15), Owner::Unmapped);
+        assert_eq!(Owner::of(BGP), Owner::Ppu);
+        assert_eq!(Owner::of(DMA), Owner::Unmapped);
+        assert_eq!(Owner::of(KEY1), Owner::Cgb);
+        assert_eq!(Owner::of(BOOT), Owner::Unmapped);
+
+        for (address, _, _) in CGB_READS {
+            assert_eq!(Owner::of(address), Owner::Cgb, "{:X}", address);
+        }
+    }
+
+    fn cgb_mmu() -> MMU {
+        let mut rom = vec![0; 0x8000];
+        rom[0x143] = 0x80;
+        let cartridge = Cartridge::new(&rom).unwrap();
+
+        MMU::new(&rom, &cartridge).unwrap()
+    }
+
+    #[test]
+    fn cgb_registers_read_back_documented_values() {
+        let mut mmu = cgb_mmu();
+
+        for (address, low, high) in CGB_READS {
+            mmu.write_byte(address, 0x00);
+            assert_eq!(mmu.read_byte(address), low, "{:X} <- 0x00", address);
+
+            mmu.write_byte(address, 0xFF);
+            assert_eq!(mmu.read_byte(address), high, "{:X} <- 0xFF", address);
+        }
+    }
+
+    #[test]
+    fn cgb_registers_are_open_bus_on_a_dmg() {
+        let rom = vec![0; 0x8000];
+        let cartridge = Cartridge::new(&rom).unwrap();
+        let mut mmu = MMU::new(&rom, &cartridge).unwrap();
+
+        for (address, _, _) in CGB_READS {
+            mmu.write_byte(address, 0x00);
+            assert_eq!(mmu.read_byte(address), 0xFF, "{:X}", address);
+        }
+    }
+
+    #[test]
+    fn palette_data_is_indexed_and_auto_increments() {
+        let mut mmu = cgb_mmu();
+
+        for (index, data) in [(BCPS, BCPD), (OCPS, OCPD)] {
+            mmu.write_byte(index, 0x80 | 0x3E);
+            mmu.write_byte(data, 0x12);
+            mmu.write_byte(data, 0x34);
+            // The index wraps within the 64 bytes
+            mmu.write_byte(data, 0x56);
+            assert_eq!(mmu.read_byte(index), 0xC1, "{:X}", index);
+
+            mmu.write_byte(index, 0x3F);
+            assert_eq!(mmu.read_byte(data), 0x34, "{:X}", data);
+            // Reading doesn't increment
+            assert_eq!(mmu.read_byte(data), 0x34, "{:X}", data);
+            mmu.write_byte(index, 0x00);
+            assert_eq!(mmu.read_byte(data), 0x56, "{:X}", data);
+            assert_eq!(mmu.read_byte(index), 0x40, "{:X}", index);
         }
     }
 }
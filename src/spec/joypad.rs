@@ -0,0 +1,165 @@
+use crate::spec::hardware_registers::io_address::P1;
+use crate::spec::hardware_registers::{Interrupt, IoHandler};
+use crate::spec::scheduler::Scheduler;
+
+const SELECT_DIRECTIONS: u8 = 0b1_0000;
+const SELECT_BUTTONS: u8 = 0b10_0000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Button {
+    Right,
+    Left,
+    Up,
+    Down,
+    A,
+    B,
+    Select,
+    Start,
+}
+
+impl Button {
+    pub const ALL: [Button; 8] = [
+        Button::Right,
+        Button::Left,
+        Button::Up,
+        Button::Down,
+        Button::A,
+        Button::B,
+        Button::Select,
+        Button::Start,
+    ];
+
+    /// The bit for this button in `Joypad::pressed`. Directions occupy the low
+    /// nibble and buttons the high nibble, in P1 line order.
+    fn mask(&self) -> u8 {
+        match self {
+            Button::Right => 0b1,
+            Button::Left => 0b10,
+            Button::Up => 0b100,
+            Button::Down => 0b1000,
+            Button::A => 0b1_0000,
+            Button::B => 0b10_0000,
+            Button::Select => 0b100_0000,
+            Button::Start => 0b1000_0000,
+        }
+    }
+}
+
+/// P1, the joypad register.
+///
+/// P1 lines are active low. Selecting a group with bits 4 and 5 connects its
+/// buttons to the low nibble.
+#[derive(Default, Hash)]
+pub struct Joypad {
+    select: u8,
+    pressed: u8,
+}
+
+impl Joypad {
+    pub fn read(&self) -> u8 {
+        0xC0 | self.select | (!self.lines() & 0xF)
+    }
+
+    /// Returns the interrupt bits requested by the write.
+    pub fn write(&mut self, value: u8) -> u8 {
+        let lines = self.lines();
+        self.select = value & (SELECT_DIRECTIONS | SELECT_BUTTONS);
+
+        self.interrupts_for(lines)
+    }
+
+    /// Returns the interrupt bits requested by the change.
+    pub fn set_button(&mut self, button: Button, pressed: bool) -> u8 {
+        let lines = self.lines();
+
+        if pressed {
+            self.pressed |= button.mask();
+        } else {
+            self.pressed &= !button.mask();
+        }
+
+        self.interrupts_for(lines)
+    }
+
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.pressed & button.mask() != 0
+    }
+
+    /// True if any selected P1 line is being pulled low.
+    pub fn input_asserted(&self) -> bool {
+        self.lines() != 0
+    }
+
+    /// The selected P1 lines that are pressed, active high.
+    fn lines(&self) -> u8 {
+        let mut lines = 0;
+
+        if self.select & SELECT_DIRECTIONS == 0 {
+            lines |= self.pressed & 0xF;
+        }
+
+        if self.select & SELECT_BUTTONS == 0 {
+            lines |= self.pressed >> 4;
+        }
+
+        lines
+    }
+
+    /// The joypad interrupt is requested when any P1 line goes from high to low.
+    fn interrupts_for(&self, previous_lines: u8) -> u8 {
+        if self.lines() & !previous_lines != 0 {
+            Interrupt::Joypad.get_position()
+        } else {
+            0
+        }
+    }
+}
+
+impl IoHandler for Joypad {
+    fn read_register(&self, address: u16, _scheduler: &Scheduler) -> u8 {
+        match address {
+            P1 => self.read(),
+            _ => unreachable!("Joypad does not own address {:X}", address),
+        }
+    }
+
+    fn write_register(&mut self, address: u16, value: u8, _scheduler: &mut Scheduler) -> u8 {
+        match address {
+            P1 => self.write(value),
+            _ => unreachable!("Joypad does not own address {:X}", address),
+        }
+    }
+}
+
+#[cfg(test)]
+mod joypad_test {
+    use crate::spec::joypad::{Button, Joypad};
+
+    #[test]
+    fn reads_selected_group_active_low() {
+        let mut joypad = Joypad::default();
+
+        joypad.set_button(Button::Start, true);
+        joypad.set_button(Button::Up, true);
+
+        joypad.write(0x10);
+        assert_eq!(joypad.read(), 0xD7);
+
+        joypad.write(0x20);
+        assert_eq!(joypad.read(), 0xEB);
+
+        joypad.write(0x30);
+        assert_eq!(joypad.read(), 0xFF);
+    }
+
+    #[test]
+    fn press_on_selected_line_requests_interrupt() {
+        let mut joypad = Joypad::default();
+
+        joypad.write(0x20);
+
+        assert_eq!(joypad.set_button(Button::A, true), 0);
+        assert_eq!(joypad.set_button(Button::Down, true), 0b1_0000);
+        assert_eq!(joypad.write(0x10), 0b1_0000);
+    }
+}
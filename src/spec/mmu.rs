@@ -1,11 +1,20 @@
 #![allow(non_camel_case_types)]
 
+use crate::device::{DeviceBus, Tick};
 use crate::mbc::rom::Rom;
 use crate::mbc::{mbc1::Mbc1, Mbc, MbcError};
-use crate::spec::cartridge_header::CartridgeType;
-use crate::spec::hardware_registers::{HardwareRegister, HardwareRegisterError, Interrupt};
-use crate::spec::memory_region::MemoryRegion;
+use crate::spec::bus::{Access, AccessKind, Bus, CodeRegion};
+use crate::spec::cartridge_header::{Cartridge, CartridgeType};
+use crate::spec::cgb::CgbRegisters;
+use crate::spec::clock::SpeedMode;
+use crate::spec::hardware_registers::io_address::{DIV, DMA};
+use crate::spec::hardware_registers::{Interrupt, IoRegisters};
+use crate::spec::joypad::Button;
+use crate::spec::ppu::OAM_SIZE;
+use crate::spec::scheduler::{Event, Scheduler};
+use std::cell::Cell;
 use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
 use std::ops::Range;
 
 #[derive(Debug)]
@@ -14,9 +23,20 @@ pub enum Error {
     ReadError,
     WriteError,
     MBCError(MbcError),
-    HWError(HardwareRegisterError),
     UnusableWriteRegion,
     InvalidInterruptFlagState,
+    UnsupportedMbc(&'static str),
+    /// A read from an address that nothing responds to, in strict mode.
+    UnmappedRead {
+        address: u16,
+        pc: u16,
+    },
+    /// A write to an address that nothing responds to, in strict mode.
+    UnmappedWrite {
+        address: u16,
+        value: u8,
+        pc: u16,
+    },
 }
 
 impl From<MbcError> for Error {
@@ -25,12 +45,6 @@ impl From<MbcError> for Error {
     }
 }
 
-impl From<HardwareRegisterError> for Error {
-    fn from(e: HardwareRegisterError) -> Self {
-        Error::HWError(e)
-    }
-}
-
 pub enum MbcType {
     Rom,
     Mbc1,
@@ -72,151 +86,434 @@ impl From<&CartridgeType> for MbcType {
 
 pub struct MMU {
     mbc: Box<dyn Mbc>,
-    pub enable_interrupts: bool,
-    interrupt_enable: u8,
+    pub(crate) rom: Box<[u8]>,
+    /// Where each 16kB of 0x0000-0x7FFF starts in `rom`, as banked by the MBC.
+    rom_offsets: [usize; 2],
     pub internal_ram: Box<[u8]>,
     hi_ram: Box<[u8]>,
-    hw_registers: HardwareRegister,
+    pub(crate) io: IoRegisters,
+    pub(crate) scheduler: Scheduler,
+    pub(crate) devices: DeviceBus,
+    dma_source: u8,
+    dma_active: bool,
+    strict: bool,
+    instruction_pc: u16,
+    /// Accesses the CPU has made during the current step, each taking an M-cycle.
+    step_accesses: u64,
+    /// T-cycles of the current step already run, to catch up with an IO access.
+    step_synced: u64,
+    /// The first unmapped access made in strict mode since `take_error`.
+    error: Cell<Option<Error>>,
+    /// Writes to each page of video, work and high ram, with echo ram counted
+    /// as the work ram it mirrors.
+    page_writes: Box<[u32]>,
+    /// Writes to the MBC, any of which could switch the ROM banks.
+    mbc_writes: u32,
 }
 
+/// The value read from an address that nothing drives.
+pub const OPEN_BUS: u8 = 0xFF;
+
+const DMA_DURATION: u64 = 640;
+
 impl MMU {
-    pub fn new(game_data: &[u8], cart_type: &CartridgeType) -> Result<MMU, Error> {
-        Ok(MMU {
-            mbc: Self::create_mbc_from_type(cart_type, game_data),
-            enable_interrupts: false,
-            interrupt_enable: 0,
+    pub fn new(game_data: &[u8], cartridge: &Cartridge) -> Result<MMU, Error> {
+        let mut mmu = MMU {
+            mbc: Self::create_mbc_from_type(&cartridge.cartridge_type, game_data.len())?,
+            rom: Box::from(game_data),
+            rom_offsets: [0, 0x4000],
             internal_ram: Box::from([0; 0xE000 - 0xC000]),
             hi_ram: Box::from([0; 0xFFFF - 0xFF80]),
-            hw_registers: HardwareRegister::default(),
-        })
+            io: IoRegisters {
+                cgb: CgbRegisters::new(cartridge.cgb),
+                ..IoRegisters::default()
+            },
+            scheduler: Scheduler::default(),
+            devices: DeviceBus::default(),
+            dma_source: 0,
+            dma_active: false,
+            strict: false,
+            instruction_pc: 0,
+            step_accesses: 0,
+            step_synced: 0,
+            error: Cell::new(None),
+            page_writes: vec![0; 0x100].into_boxed_slice(),
+            mbc_writes: 0,
+        };
+
+        mmu.rom_offsets = mmu.mbc.rom_offsets();
+        mmu.io.ppu.power_on(&mut mmu.scheduler);
+        mmu.io.timer.schedule_frame_sequencer(&mut mmu.scheduler);
+
+        Ok(mmu)
     }
 
-    pub fn read_byte(&self, address: u16) -> Result<u8, Error> {
-        match address {
-            0x8000..=0x9FFF => {
-                // VIDEO RAM
-                Ok(0)
+    /// Moves time forward to the end of a step that took `t_cycles`, and runs every
+    /// component event that has come due. Whatever part of the step already ran,
+    /// to catch up with an IO access the CPU made partway through, isn't run again.
+    pub fn advance(&mut self, t_cycles: u64) {
+        let remaining = t_cycles.saturating_sub(self.step_synced);
+
+        self.step_accesses = 0;
+        self.step_synced = 0;
+        self.run_components(remaining);
+    }
+
+    /// IO registers can change within an instruction, so before the CPU accesses
+    /// one, the rest of the system is brought up to the M-cycle the access happens
+    /// on.
+    fn sync_for_access(&mut self, address: u16) {
+        if !(0xFF00..0xFF80).contains(&address) {
+            return;
+        }
+
+        let at = self.step_accesses * 4;
+
+        if at > self.step_synced {
+            self.run_components(at - self.step_synced);
+            self.step_synced = at;
+        }
+    }
+
+    fn run_components(&mut self, t_cycles: u64) {
+        self.scheduler.advance(t_cycles);
+
+        while let Some((event, at)) = self.scheduler.pop_due() {
+            let interrupts = match event {
+                Event::TimerOverflow => self.io.timer.overflow(at, &mut self.scheduler),
+                Event::PpuMode => self.io.ppu.next_mode(at, &mut self.scheduler),
+                Event::DmaComplete => {
+                    self.dma_active = false;
+                    0
+                }
+                Event::SerialTransfer => {
+                    let incoming = self.devices.exchange(self.io.serial.outgoing());
+                    self.io.serial.complete_transfer(incoming)
+                }
+                Event::FrameSequencer => {
+                    self.io.apu.clock_frame_sequencer();
+                    self.io.timer.schedule_frame_sequencer(&mut self.scheduler);
+                    0
+                }
+                // Devices are ticked below either way
+                Event::Device => 0,
+            };
+
+            self.io.interrupts.request(interrupts);
+        }
+
+        if self.io.serial.waiting_for_external_clock() {
+            if let Some(incoming) = self.devices.poll_external(self.io.serial.outgoing()) {
+                let interrupts = self.io.serial.complete_transfer(incoming);
+                self.io.interrupts.request(interrupts);
             }
-            0xC000..=0xFDFF => {
-                // Internal work ram
-                // Note 0xE000-0xFDFF is mirror ram
-                let mirrored_address = if address >= 0xE000 {
-                    address - (0xE000 - 0xC000)
-                } else {
-                    address
-                };
+        }
 
-                Ok(self.internal_ram[(mirrored_address - 0xC000) as usize])
+        let interrupts = self.devices.tick(&Tick {
+            now: self.scheduler.now(),
+            t_cycles,
+            serial_sent: self.io.serial.take_sent(),
+        });
+        self.io.interrupts.request(interrupts);
+        self.schedule_devices();
+    }
+
+    /// Schedules `Event::Device` for when the attached devices next need ticking.
+    /// Needed whenever a device is attached.
+    pub fn schedule_devices(&mut self) {
+        match self.devices.next_tick(self.scheduler.now()) {
+            Some(at) => self.scheduler.schedule(Event::Device, at),
+            None => self.scheduler.cancel(Event::Device),
+        }
+    }
+
+    /// Lets `t_cycles` pass with the system clock stopped, as it is during STOP. The
+    /// timer, PPU, DMA and serial port all stay where they are, and devices aren't
+    /// ticked.
+    pub fn advance_stopped(&mut self, t_cycles: u64) {
+        self.io.timer.postpone(t_cycles);
+        self.scheduler.postpone(t_cycles);
+    }
+
+    /// Accesses can't fail. In strict mode, unmapped accesses read open bus as usual
+    /// and are reported by `take_error`.
+    pub fn read_byte(&self, address: u16) -> u8 {
+        if let Some(value) = self.devices.read(address) {
+            return value;
+        }
+
+        match address >> 12 {
+            0x0..=0x7 => {
+                let offset =
+                    self.rom_offsets[(address >> 14) as usize] + (address & 0x3FFF) as usize;
+
+                match self.rom.get(offset) {
+                    Some(&value) => value,
+                    None => self.unmapped_read(address),
+                }
             }
-            0xFEA0..=0xFEFF => Ok(0),
-            0xFF00..=0xFF7F => Ok(self.hw_registers.map_read(address)?),
-            0xFF80..=0xFFFE => Ok(self.hi_ram[(address - 0xFF80) as usize]),
-            0xFFFF => Ok(self.interrupt_enable),
-            _ => self
-                .mbc
-                .map_read(address)
-                .or_else(|_| panic!("Attempt to read from an unknown address {:X}", address)),
+            0x8 | 0x9 => self.io.ppu.vram[(address - 0x8000) as usize],
+            0xA | 0xB => match self.mbc.map_read(address) {
+                Ok(value) => value,
+                Err(_) => self.unmapped_read(address),
+            },
+            // Internal work ram
+            0xC | 0xD => self.internal_ram[(address - 0xC000) as usize],
+            // Mirror ram
+            0xE => self.internal_ram[(address - 0xE000) as usize],
+            _ => self.read_high_page(address),
         }
     }
 
-    pub fn write_byte(&mut self, address: u16, value: u8) -> Result<(), Error> {
+    /// 0xF000-0xFFFF, where everything that isn't mirror ram lives.
+    fn read_high_page(&self, address: u16) -> u8 {
         match address {
-            0x8000..=0x9FFF => {
-                // VIDEO RAM unimplemented
-                Ok(())
-            }
-            0xC000..=0xFDFF => {
-                // Internal work ram
-                // Note 0xE000-0xFDFF is mirror ram
-                let mirrored_address = if address >= 0xE000 {
-                    address - (0xE000 - 0xC000)
-                } else {
-                    address
-                };
+            0xF000..=0xFDFF => self.internal_ram[(address - 0xE000) as usize],
+            0xFE00..=0xFE9F if self.dma_active => OPEN_BUS,
+            0xFE00..=0xFE9F => self.io.ppu.oam[(address - 0xFE00) as usize],
+            0xFEA0..=0xFEFF => OPEN_BUS,
+            DMA => self.dma_source,
+            0xFF00..=0xFF7F | 0xFFFF => self.io.read(address, &self.scheduler),
+            _ => self.hi_ram[(address - 0xFF80) as usize],
+        }
+    }
+
+    pub fn write_byte(&mut self, address: u16, value: u8) {
+        if self.devices.write(address, value) {
+            return;
+        }
+
+        if let 0x8000..=0x9FFF | 0xC000..=0xFDFF | 0xFF80..=0xFFFE = address {
+            let writes = &mut self.page_writes[Self::ram_page(address)];
+            *writes = writes.wrapping_add(1);
+        }
 
-                self.internal_ram[(mirrored_address - 0xC000) as usize] = value;
-                Ok(())
+        match address >> 12 {
+            0x0..=0x7 => {
+                self.write_mbc(address, value);
+                self.rom_offsets = self.mbc.rom_offsets();
+                self.mbc_writes = self.mbc_writes.wrapping_add(1);
             }
-            0xFEA0..=0xFEFF => Ok(()),
-            0xFF00..=0xFF7F => Ok(self.hw_registers.map_write(address, value)?),
-            0xFF80..=0xFFFE => {
-                self.hi_ram[(address - 0xFF80) as usize] = value;
-                Ok(())
+            0x8 | 0x9 => self.io.ppu.vram[(address - 0x8000) as usize] = value,
+            0xA | 0xB => self.write_mbc(address, value),
+            // Internal work ram
+            0xC | 0xD => self.internal_ram[(address - 0xC000) as usize] = value,
+            // Mirror ram
+            0xE => self.internal_ram[(address - 0xE000) as usize] = value,
+            _ => self.write_high_page(address, value),
+        }
+    }
+
+    fn write_high_page(&mut self, address: u16, value: u8) {
+        match address {
+            0xF000..=0xFDFF => self.internal_ram[(address - 0xE000) as usize] = value,
+            0xFE00..=0xFE9F if self.dma_active => {}
+            0xFE00..=0xFE9F => self.io.ppu.oam[(address - 0xFE00) as usize] = value,
+            0xFEA0..=0xFEFF => {}
+            DMA => {
+                self.dma_source = value;
+                self.start_oam_dma(value);
             }
-            0xFFFF => {
-                self.interrupt_enable = value;
+            0xFF00..=0xFF7F | 0xFFFF => self.io.write(address, value, &mut self.scheduler),
+            _ => self.hi_ram[(address - 0xFF80) as usize] = value,
+        }
+    }
+
+    fn write_mbc(&mut self, address: u16, value: u8) {
+        if self.mbc.map_write(address, value).is_err() && self.strict {
+            self.report(Error::UnmappedWrite {
+                address,
+                value,
+                pc: self.instruction_pc,
+            });
+        }
+    }
+
+    fn unmapped_read(&self, address: u16) -> u8 {
+        if self.strict {
+            self.report(Error::UnmappedRead {
+                address,
+                pc: self.instruction_pc,
+            });
+        }
+
+        OPEN_BUS
+    }
+
+    fn report(&self, error: Error) {
+        let first = self.error.take().unwrap_or(error);
+        self.error.set(Some(first));
+    }
 
-                Ok(())
+    /// Code in ROM only changes with the bank, and work and high ram can be read
+    /// without side effects. Everything else, and anything a device claims, isn't cached.
+    pub fn code_region(&self, address: u16) -> CodeRegion {
+        if self.devices.claims(address) {
+            return CodeRegion::Uncached;
+        }
+
+        match address >> 12 {
+            0x0..=0x7 => {
+                let bank = self.rom_offsets[(address >> 14) as usize];
+
+                if bank + ((address & 0x3FFF) as usize) < self.rom.len() {
+                    CodeRegion::Rom(bank)
+                } else {
+                    CodeRegion::Uncached
+                }
             }
-            _ => self.mbc.map_write(address, value).or_else(|_| {
-                panic!(
-                    "Attempt to write to an unknown address {:X} <- {:X}",
-                    address, value
-                )
-            }),
+            0x8 | 0x9 | 0xC..=0xE => CodeRegion::Ram,
+            _ => match address {
+                0xF000..=0xFDFF | 0xFF80..=0xFFFE => CodeRegion::Ram,
+                _ => CodeRegion::Uncached,
+            },
+        }
+    }
+
+    fn ram_page(address: u16) -> usize {
+        match address {
+            0xE000..=0xFDFF => ((address - 0x2000) >> 8) as usize,
+            _ => (address >> 8) as usize,
+        }
+    }
+
+    /// ROM counts as written whenever the MBC is, since the banks could have changed.
+    pub fn page_writes(&self, address: u16) -> u32 {
+        match address {
+            0x0000..=0x7FFF => self.mbc_writes,
+            _ => self.page_writes[Self::ram_page(address)],
+        }
+    }
+
+    /// Puts everything back the way `new` left it, except for what's plugged in:
+    /// the link cable, devices, held buttons and, with `keep_cartridge`, the MBC
+    /// along with its RAM.
+    pub fn restart(&mut self, cartridge: &Cartridge, keep_cartridge: bool) -> Result<(), Error> {
+        let mut mmu = MMU::new(&self.rom, cartridge)?;
+
+        if keep_cartridge {
+            std::mem::swap(&mut mmu.mbc, &mut self.mbc);
+            mmu.rom_offsets = mmu.mbc.rom_offsets();
+        }
+
+        mmu.devices = std::mem::take(&mut self.devices);
+        mmu.schedule_devices();
+        mmu.strict = self.strict;
+
+        for button in Button::ALL {
+            mmu.set_button(button, self.io.joypad.is_pressed(button));
         }
+
+        *self = mmu;
+
+        Ok(())
+    }
+
+    /// The first unmapped access made in strict mode since this was last called.
+    pub fn take_error(&self) -> Option<Error> {
+        self.error.take()
     }
 
-    pub fn read_word(&self, address: u16) -> Result<u16, Error> {
-        let rhs = self.read_byte(address)? as u16;
-        let lhs = self.read_byte(address + 1)? as u16;
-        let value = (lhs << 8) | rhs;
+    pub fn read_word(&self, address: u16) -> u16 {
+        let rhs = self.read_byte(address) as u16;
+        let lhs = self.read_byte(address.wrapping_add(1)) as u16;
 
-        Ok(value)
+        (lhs << 8) | rhs
     }
 
-    pub fn write_word(&mut self, address: u16, value: u16) -> Result<(), Error> {
+    pub fn write_word(&mut self, address: u16, value: u16) {
         let rhs = value & 0xFF;
         let lhs = (value & 0xFF00) >> 8;
 
-        self.write_byte(address, rhs as u8)?;
-        self.write_byte(address + 1, lhs as u8)
+        self.write_byte(address, rhs as u8);
+        self.write_byte(address.wrapping_add(1), lhs as u8)
     }
 
-    pub fn write_interrupt_enable_reg(&mut self, value: bool) {
-        self.enable_interrupts = value;
+    /// The highest priority interrupt that is both requested and enabled in IE,
+    /// regardless of IME.
+    pub fn pending_interrupt(&self) -> Option<Interrupt> {
+        Interrupt::try_from(self.io.interrupts.pending()).ok()
     }
 
-    pub fn interrupts_enabled(&self) -> Result<Option<Interrupt>, Error> {
-        let interrupt_enable = self.read_byte(0xFFFF)?;
-        let interrupt_flag = self.read_byte(0xFF0F)?;
+    pub fn interrupts_scheduled(&self) -> bool {
+        self.io.interrupts.pending() != 0
+    }
 
-        if self.enable_interrupts && (interrupt_enable & interrupt_flag) != 0 {
-            return Ok(Interrupt::try_from(interrupt_enable & interrupt_flag).ok());
+    pub fn set_interrupt_bit(&mut self, int: Interrupt, state: bool) {
+        if state {
+            self.io.interrupts.request(int.get_position());
+        } else {
+            self.io.interrupts.acknowledge(&int);
         }
+    }
 
-        Ok(None)
+    /// In strict mode, accesses to unmapped memory still behave like open bus, but
+    /// also queue `Error::UnmappedRead` or `Error::UnmappedWrite`. The CPU picks it
+    /// up through `take_error` once the instruction has finished.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
     }
 
-    pub fn interrupts_scheduled(&self) -> Result<bool, Error> {
-        let interrupt_enable = self.read_byte(0xFFFF)?;
-        let interrupt_flag = self.read_byte(0xFF0F)?;
+    /// Records the address of the instruction being executed, so that errors in
+    /// strict mode can report where the access came from.
+    pub fn set_instruction_pc(&mut self, pc: u16) {
+        self.instruction_pc = pc;
+    }
 
-        Ok((interrupt_enable & interrupt_flag) != 0)
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        let interrupts = self.io.joypad.set_button(button, pressed);
+        self.io.interrupts.request(interrupts);
     }
 
-    pub fn set_interrupt_bit(&mut self, int: Interrupt, state: bool) -> Result<(), Error> {
-        let interrupt_flag = self.read_byte(0xFF0F)?;
-        let bit = int.get_position();
-        let next_value = if state {
-            interrupt_flag | bit
-        } else {
-            interrupt_flag & !bit
-        };
+    pub fn speed_mode(&self) -> SpeedMode {
+        self.io.cgb.speed_mode()
+    }
 
-        self.write_byte(0xFF0F, next_value)
+    pub fn speed_switch_armed(&self) -> bool {
+        self.io.cgb.speed_switch_armed()
     }
 
-    fn create_mbc_from_type(cart_type: &CartridgeType, data: &[u8]) -> Box<dyn Mbc> {
+    /// Toggles between single and double speed, as requested through KEY1.
+    pub fn switch_speed(&mut self) {
+        self.io.cgb.switch_speed();
+
+        let double_speed = self.speed_mode() == SpeedMode::Double;
+        self.io.ppu.set_double_speed(double_speed);
+        self.io
+            .timer
+            .set_double_speed(double_speed, &mut self.scheduler);
+    }
+
+    /// Copies 0xA0 bytes from `source << 8` into OAM. OAM is inaccessible to the CPU
+    /// until the transfer completes.
+    fn start_oam_dma(&mut self, source: u8) {
+        let base = (source as u16) << 8;
+        let mut data = [0; OAM_SIZE];
+
+        self.dma_active = false;
+
+        for (offset, byte) in data.iter_mut().enumerate() {
+            *byte = self.read_byte(base + offset as u16);
+        }
+
+        self.io.ppu.oam = data;
+        self.dma_active = true;
+        self.scheduler.schedule_in(Event::DmaComplete, DMA_DURATION);
+    }
+
+    fn create_mbc_from_type(
+        cart_type: &CartridgeType,
+        rom_size: usize,
+    ) -> Result<Box<dyn Mbc>, Error> {
         match MbcType::from(cart_type) {
-            MbcType::Rom => Box::new(Rom::new(data)),
-            MbcType::Mbc1 => Box::new(Mbc1::new(data)),
-            MbcType::Mbc2 => unimplemented!("MBC2"),
-            MbcType::Mbc3 => unimplemented!("MBC3"),
-            MbcType::Mbc4 => unimplemented!("MBC4"),
-            MbcType::Mbc5 => unimplemented!("MBC5"),
-            MbcType::Mbc5Rumble => unimplemented!("MBC5Rumble"),
-            MbcType::Mmm => unimplemented!("MMM"),
+            MbcType::Rom => Ok(Box::new(Rom::new())),
+            MbcType::Mbc1 => Ok(Box::new(Mbc1::new(rom_size))),
+            MbcType::Mbc2 => Err(Error::UnsupportedMbc("MBC2")),
+            MbcType::Mbc3 => Err(Error::UnsupportedMbc("MBC3")),
+            MbcType::Mbc4 => Err(Error::UnsupportedMbc("MBC4")),
+            MbcType::Mbc5 => Err(Error::UnsupportedMbc("MBC5")),
+            MbcType::Mbc5Rumble => Err(Error::UnsupportedMbc("MBC5Rumble")),
+            MbcType::Mmm => Err(Error::UnsupportedMbc("MMM")),
         }
     }
 
@@ -226,13 +523,301 @@ impl MMU {
         let x = range.clone();
 
         for address in range {
-            chunk.push(format!(
-                "{:X}: {:X}",
-                address,
-                self.read_byte(address).unwrap()
-            ))
+            chunk.push(format!("{:X}: {:X}", address, self.read_byte(address)))
         }
 
         println!("Chunk between {:X?}: {:?}", x, chunk);
     }
 }
+
+/// Everything that affects emulation. ROM is left out since it can't change, and
+/// so is the bookkeeping for strict mode and the block cache.
+impl Hash for MMU {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.mbc.hash_state(state);
+        self.internal_ram.hash(state);
+        self.hi_ram.hash(state);
+        self.io.hash(state);
+        self.scheduler.hash(state);
+        self.devices.save_state().hash(state);
+        self.dma_source.hash(state);
+        self.dma_active.hash(state);
+    }
+}
+
+impl Bus for MMU {
+    fn read_byte(&self, address: u16) -> u8 {
+        MMU::read_byte(self, address)
+    }
+
+    fn write_byte(&mut self, address: u16, value: u8) {
+        MMU::write_byte(self, address, value)
+    }
+
+    fn take_error(&mut self) -> Option<Error> {
+        MMU::take_error(self)
+    }
+
+    fn code_region(&self, address: u16) -> CodeRegion {
+        MMU::code_region(self, address)
+    }
+
+    fn page_writes(&self, address: u16) -> u32 {
+        MMU::page_writes(self, address)
+    }
+
+    fn set_instruction_pc(&mut self, pc: u16) {
+        MMU::set_instruction_pc(self, pc)
+    }
+
+    fn pending_interrupt(&self) -> Option<Interrupt> {
+        MMU::pending_interrupt(self)
+    }
+
+    fn interrupts_scheduled(&self) -> bool {
+        MMU::interrupts_scheduled(self)
+    }
+
+    fn set_interrupt_bit(&mut self, interrupt: Interrupt, state: bool) {
+        MMU::set_interrupt_bit(self, interrupt, state)
+    }
+
+    fn input_asserted(&self) -> bool {
+        self.io.joypad.input_asserted()
+    }
+
+    fn speed_switch_armed(&self) -> bool {
+        MMU::speed_switch_armed(self)
+    }
+
+    fn switch_speed(&mut self) {
+        MMU::switch_speed(self)
+    }
+
+    fn reset_div(&mut self) {
+        MMU::write_byte(self, DIV, 0)
+    }
+
+    fn on_access(&mut self, _access: Access) {
+        self.step_accesses += 1;
+    }
+
+    fn cpu_read(&mut self, address: u16) -> u8 {
+        self.sync_for_access(address);
+
+        let value = MMU::read_byte(self, address);
+        self.on_access(Access {
+            address,
+            value,
+            kind: AccessKind::Read,
+        });
+
+        value
+    }
+
+    fn cpu_write(&mut self, address: u16, value: u8) {
+        self.sync_for_access(address);
+
+        MMU::write_byte(self, address, value);
+        self.on_access(Access {
+            address,
+            value,
+            kind: AccessKind::Write,
+        });
+    }
+}
+
+#[cfg(test)]
+mod mmu_test {
+    use crate::spec::cartridge_header::Cartridge;
+    use crate::spec::mmu::{Error, MMU, OPEN_BUS};
+
+    fn mmu_for(rom: &[u8]) -> MMU {
+        let cartridge = Cartridge::new(rom).unwrap();
+
+        MMU::new(rom, &cartridge).unwrap()
+    }
+
+    /// A ROM-only cartridge that's shorter than the 32kB the address space expects.
+    fn small_rom() -> Vec<u8> {
+        vec![0; 0x2000]
+    }
+
+    /// An MBC1 cartridge with a single 16kB bank.
+    fn mbc1_rom() -> Vec<u8> {
+        let mut rom = vec![0; 0x4000];
+        rom[0x147] = 0x01;
+
+        rom
+    }
+
+    #[test]
+    fn unmapped_reads_are_open_bus() {
+        let rom = small_rom();
+        let mut mmu = mmu_for(&rom);
+
+        assert_eq!(mmu.read_byte(0x4000), OPEN_BUS);
+        mmu.write_byte(0x4000, 0x12);
+        assert!(mmu.take_error().is_none());
+    }
+
+    #[test]
+    fn strict_mode_reports_address_and_pc() {
+        let rom = small_rom();
+        let mut mmu = mmu_for(&rom);
+
+        mmu.set_strict(true);
+        mmu.set_instruction_pc(0x150);
+
+        assert_eq!(mmu.read_byte(0x7FFF), OPEN_BUS);
+        assert!(matches!(
+            mmu.take_error(),
+            Some(Error::UnmappedRead {
+                address: 0x7FFF,
+                pc: 0x150
+            })
+        ));
+        assert!(mmu.take_error().is_none());
+    }
+
+    #[test]
+    fn strict_mode_keeps_the_first_error() {
+        let rom = small_rom();
+        let mut mmu = mmu_for(&rom);
+
+        mmu.set_strict(true);
+        mmu.read_byte(0x7FFE);
+        mmu.read_byte(0x7FFF);
+
+        assert!(matches!(
+            mmu.take_error(),
+            Some(Error::UnmappedRead {
+                address: 0x7FFE,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn mbc1_banks_past_the_end_of_rom_wrap() {
+        let mut rom = vec![0; 0x8000];
+        rom[0x147] = 0x01;
+        rom[0x4000] = 1;
+        let mut mmu = mmu_for(&rom);
+
+        mmu.write_byte(0x2000, 0x03);
+        assert_eq!(mmu.read_byte(0x4000), 1);
+        mmu.write_byte(0x2000, 0x02);
+        assert_eq!(mmu.read_byte(0x4000), 0);
+
+        // The upper bits wrap too, in either banking mode
+        mmu.write_byte(0x2000, 0x01);
+        mmu.write_byte(0x4000, 0x01);
+        mmu.write_byte(0x6000, 0x01);
+        assert_eq!(mmu.read_byte(0x4000), 1);
+        assert_eq!(mmu.read_byte(0x0000), 0);
+    }
+
+    #[test]
+    fn frame_sequencer_steps_on_falling_edges_of_div_bit_12() {
+        let rom = small_rom();
+        let mut mmu = mmu_for(&rom);
+
+        // The system counter starts at 0xABCC, so bit 12 next falls at 0xC000
+        mmu.advance(0xC000 - 0xABCC - 1);
+        assert_eq!(mmu.io.apu.frame_sequencer_step(), 0);
+        mmu.advance(1);
+        assert_eq!(mmu.io.apu.frame_sequencer_step(), 1);
+        mmu.advance(0x2000);
+        assert_eq!(mmu.io.apu.frame_sequencer_step(), 2);
+
+        // Resetting DIV while bit 12 is high makes it fall early
+        mmu.advance(0x1000);
+        mmu.write_byte(0xFF04, 0);
+        mmu.advance(0);
+        assert_eq!(mmu.io.apu.frame_sequencer_step(), 3);
+        mmu.advance(0x1FFF);
+        assert_eq!(mmu.io.apu.frame_sequencer_step(), 3);
+        mmu.advance(1);
+        assert_eq!(mmu.io.apu.frame_sequencer_step(), 4);
+
+        // Powering the APU back on starts it over
+        mmu.write_byte(0xFF26, 0);
+        mmu.write_byte(0xFF26, 0x80);
+        assert_eq!(mmu.io.apu.frame_sequencer_step(), 0);
+    }
+
+    #[test]
+    fn mbc1_switches_the_upper_rom_bank() {
+        let mut rom = vec![0; 0x10000];
+        rom[0x147] = 0x01;
+        rom[0x4000] = 1;
+        rom[0x8000] = 2;
+        rom[0xC000] = 3;
+        let mut mmu = mmu_for(&rom);
+
+        assert_eq!(mmu.read_byte(0x4000), 1);
+
+        for bank in 1..=3 {
+            mmu.write_byte(0x2000, bank);
+            assert_eq!(mmu.read_byte(0x4000), bank);
+            assert_eq!(mmu.read_byte(0x0000), 0);
+        }
+
+        mmu.write_byte(0x2000, 0x00);
+        assert_eq!(mmu.read_byte(0x4000), 1);
+    }
+
+    #[test]
+    fn mbc1_upper_bits_select_banks_past_0x1f() {
+        let mut rom = vec![0; 0x22 * 0x4000];
+        rom[0x147] = 0x01;
+        rom[0x20 * 0x4000] = 0x20;
+        rom[0x21 * 0x4000] = 0x21;
+        let mut mmu = mmu_for(&rom);
+
+        mmu.write_byte(0x4000, 0x01);
+        assert_eq!(mmu.read_byte(0x4000), 0x21);
+        assert_eq!(mmu.read_byte(0x0000), 0);
+
+        // Banking mode 1 applies the upper bits to 0x0000-0x3FFF too
+        mmu.write_byte(0x6000, 0x01);
+        assert_eq!(mmu.read_byte(0x0000), 0x20);
+    }
+
+    #[test]
+    fn mbc1_ram_is_open_bus_until_enabled() {
+        let rom = mbc1_rom();
+        let mut mmu = mmu_for(&rom);
+
+        mmu.write_byte(0xA000, 0x42);
+        assert_eq!(mmu.read_byte(0xA000), OPEN_BUS);
+
+        mmu.write_byte(0x0000, 0x0A);
+        mmu.write_byte(0xA000, 0x42);
+        assert_eq!(mmu.read_byte(0xA000), 0x42);
+
+        mmu.set_strict(true);
+        mmu.write_byte(0x0000, 0);
+        assert!(mmu.take_error().is_none());
+
+        mmu.write_byte(0xA000, 0x42);
+        assert!(matches!(
+            mmu.take_error(),
+            Some(Error::UnmappedWrite {
+                address: 0xA000,
+                value: 0x42,
+                ..
+            })
+        ));
+
+        mmu.read_byte(0xA000);
+        assert!(matches!(
+            mmu.take_error(),
+            Some(Error::UnmappedRead {
+                address: 0xA000,
+                ..
+            })
+        ));
+    }
+}
@@ -1,14 +1,23 @@
+pub mod apu;
+pub mod block_cache;
+pub mod bus;
 pub mod cartridge_header;
+pub mod cgb;
 pub mod clock;
 pub mod cpu;
 pub mod gameboy;
 pub mod hardware_registers;
+pub mod joypad;
 pub mod jump_condition;
 pub mod memory_region;
 pub mod mmu;
 pub mod mnemonic;
 pub mod opcode;
+pub mod ppu;
 pub mod register;
+pub mod scheduler;
+pub mod serial;
+pub mod timer;
 
 mod opcodes;
 mod register_ops;
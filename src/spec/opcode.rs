@@ -192,7 +192,6 @@ impl Instruction {
             | Instruction::DI
             | Instruction::EI
             | Instruction::HALT
-            | Instruction::STOP
             | Instruction::JP_HL
             | Instruction::LD_RR
             | Instruction::LD_RHL
@@ -231,6 +230,7 @@ impl Instruction {
             | Instruction::RES_NR
             | Instruction::JR_PCDD
             | Instruction::JR_FPCDD
+            | Instruction::STOP
             | Instruction::LD_RN
             | Instruction::LD_HLN
             | Instruction::LD_AN
@@ -735,7 +735,8 @@ pub fn instruction_lookup(byte: u8, cb_byte: Option<u8>) -> OpcodeLookupResult {
         0xC8 => Ok(Instruction::RET_F),
         0xC9 => Ok(Instruction::RET),
         0xCA => Ok(Instruction::JP_FNN),
-        0xCB => unreachable!(),
+        // Only valid with the byte that follows it
+        0xCB => Err(OpcodeError::InvalidOpcodeInput),
         0xCC => Ok(Instruction::CALL_FNN),
         0xCD => Ok(Instruction::CALL_NN),
         0xCE => Ok(Instruction::ADC_AN),
@@ -1,129 +1,141 @@
-use crate::dasm::InstructionData;
+use crate::dasm::Operands;
 
+use crate::spec::bus::Bus;
 use crate::spec::cpu::{Error, TStackable, CPU};
-use crate::spec::mmu::MMU;
-use crate::spec::mnemonic::Mnemonic;
-use crate::spec::opcode::Instruction;
-use crate::spec::opcodes::unexpected_op;
 use crate::spec::register::TRegister;
 use crate::util::byte_ops::hi_lo_combine;
 
 impl CPU {
-    pub(crate) fn evaluate_branch(
+    pub(crate) fn jp_nn(&mut self, opcode_data: &[u8; 2]) -> Result<u8, Error> {
+        let address = hi_lo_combine(opcode_data[1], opcode_data[0]);
+
+        self.registers.pc.set_value(address);
+        Ok(4)
+    }
+
+    pub(crate) fn jp_hl(&mut self) -> Result<u8, Error> {
+        self.registers.pc.set_value(self.registers.hl());
+
+        Ok(1)
+    }
+
+    pub(crate) fn jp_fnn(
+        &mut self,
+        operands: &Operands,
+        opcode_data: &[u8; 2],
+    ) -> Result<u8, Error> {
+        let cc = operands.condition;
+        let address = hi_lo_combine(opcode_data[1], opcode_data[0]);
+
+        if self.registers.jump_condition(cc)? {
+            self.registers.pc.set_value(address);
+
+            return Ok(4);
+        }
+
+        Ok(3)
+    }
+
+    pub(crate) fn jr_pcdd(&mut self, opcode_data: &[u8; 2]) -> Result<u8, Error> {
+        let offset = (opcode_data[0] as i8) as i16;
+        // println!("\t\tPC:{:X}+{} ({})", *self.registers.pc.get_value() as i16, offset as i8, offset);
+        self.registers.pc.update_value_checked(|last_val| {
+            let result = ((*last_val) as i16).checked_add(offset).map(|x| x as u16);
+            // println!("\t\t\t PC{:X?}", result);
+            Ok(result)
+        })?;
+        Ok(3)
+    }
+
+    pub(crate) fn jr_fpcdd(
         &mut self,
-        instruction_data: &InstructionData,
+        operands: &Operands,
         opcode_data: &[u8; 2],
-        mmu: &mut MMU,
     ) -> Result<u8, Error> {
-        match instruction_data.instruction {
-            Instruction::JP_NN => {
-                let address = hi_lo_combine(opcode_data[1], opcode_data[0]);
-
-                self.registers.pc.set_value(address);
-                Ok(4)
-            }
-            Instruction::JP_HL => {
-                self.registers.pc.set_value(self.registers.hl());
-
-                Ok(1)
-            }
-            Instruction::JP_FNN => {
-                let cc = instruction_data.opcode_info.hi & 0b011;
-                let address = hi_lo_combine(opcode_data[1], opcode_data[0]);
-
-                if self.registers.jump_condition(cc)? {
-                    self.registers.pc.set_value(address);
-
-                    return Ok(4);
-                }
-
-                Ok(3)
-            }
-            Instruction::JR_PCDD => {
-                let offset = (opcode_data[0] as i8) as i16;
-                // println!("\t\tPC:{:X}+{} ({})", *self.registers.pc.get_value() as i16, offset as i8, offset);
-                self.registers.pc.update_value_checked(|last_val| {
-                    let result = ((*last_val) as i16).checked_add(offset).map(|x| x as u16);
-                    // println!("\t\t\t PC{:X?}", result);
-                    Ok(result)
-                })?;
-                Ok(3)
-            }
-            Instruction::JR_FPCDD => {
-                let cc = instruction_data.opcode_info.hi & 0b011;
-                let data = opcode_data[0];
-
-                if self.registers.jump_condition(cc)? {
-                    let val = (data as i8) as i16;
-
-                    // println!("\t\tPC:{:X}+{} ({})", *self.registers.pc.get_value() as i16, data as i8, val);
-                    self.registers.pc.update_value_checked(|last| {
-                        let result = ((*last) as i16).checked_add(val).map(|x| x as u16);
-                        // println!("\t\t\t PC{:X?}", result);
-                        Ok(result)
-                    })?;
-
-                    return Ok(3);
-                }
-
-                Ok(2)
-            }
-            Instruction::CALL_NN => {
-                self.push_stack_word(*self.registers.pc.get_value(), mmu)?;
-                self.registers
-                    .pc
-                    .set_value(hi_lo_combine(opcode_data[1], opcode_data[0]));
-
-                Ok(6)
-            }
-            Instruction::CALL_FNN => {
-                let cc = instruction_data.opcode_info.hi & 0b11;
-
-                if self.registers.jump_condition(cc)? {
-                    self.push_stack_word(*self.registers.pc.get_value(), mmu)?;
-                    self.registers
-                        .pc
-                        .set_value(hi_lo_combine(opcode_data[1], opcode_data[0]));
-
-                    return Ok(6);
-                }
-
-                Ok(3)
-            }
-            Instruction::RET => {
-                let stack_val = self.pop_stack_word(mmu)?;
-                self.registers.pc.set_value(stack_val);
-
-                Ok(4)
-            }
-            Instruction::RET_F => {
-                let cc = instruction_data.opcode_info.hi & 0b011;
-
-                if self.registers.jump_condition(cc)? {
-                    let stack_val = self.pop_stack_word(mmu)?;
-                    self.registers.pc.set_value(stack_val);
-
-                    return Ok(5);
-                }
-
-                Ok(2)
-            }
-            Instruction::RETI => {
-                let stack_val = self.pop_stack_word(mmu)?;
-
-                mmu.write_interrupt_enable_reg(true);
-                self.registers.pc.set_value(stack_val);
-
-                Ok(4)
-            }
-            Instruction::RST => {
-                self.push_stack_word(*self.registers.pc.get_value(), mmu)?;
-                self.registers
-                    .pc
-                    .set_value((instruction_data.opcode_info.hi as u16) * 8);
-                Ok(4)
-            }
-            _ => Err(unexpected_op(&instruction_data.mnemonic, &Mnemonic::PUSH)),
+        let cc = operands.condition;
+        let data = opcode_data[0];
+
+        if self.registers.jump_condition(cc)? {
+            let val = (data as i8) as i16;
+
+            // println!("\t\tPC:{:X}+{} ({})", *self.registers.pc.get_value() as i16, data as i8, val);
+            self.registers.pc.update_value_checked(|last| {
+                let result = ((*last) as i16).checked_add(val).map(|x| x as u16);
+                // println!("\t\t\t PC{:X?}", result);
+                Ok(result)
+            })?;
+
+            return Ok(3);
         }
+
+        Ok(2)
+    }
+
+    pub(crate) fn call_nn<B: Bus>(
+        &mut self,
+        opcode_data: &[u8; 2],
+        bus: &mut B,
+    ) -> Result<u8, Error> {
+        self.push_stack_word(*self.registers.pc.get_value(), bus)?;
+        self.registers
+            .pc
+            .set_value(hi_lo_combine(opcode_data[1], opcode_data[0]));
+
+        Ok(6)
+    }
+
+    pub(crate) fn call_fnn<B: Bus>(
+        &mut self,
+        operands: &Operands,
+        opcode_data: &[u8; 2],
+        bus: &mut B,
+    ) -> Result<u8, Error> {
+        let cc = operands.condition;
+
+        if self.registers.jump_condition(cc)? {
+            self.push_stack_word(*self.registers.pc.get_value(), bus)?;
+            self.registers
+                .pc
+                .set_value(hi_lo_combine(opcode_data[1], opcode_data[0]));
+
+            return Ok(6);
+        }
+
+        Ok(3)
+    }
+
+    pub(crate) fn ret<B: Bus>(&mut self, bus: &mut B) -> Result<u8, Error> {
+        let stack_val = self.pop_stack_word(bus)?;
+        self.registers.pc.set_value(stack_val);
+
+        Ok(4)
+    }
+
+    pub(crate) fn ret_f<B: Bus>(&mut self, operands: &Operands, bus: &mut B) -> Result<u8, Error> {
+        let cc = operands.condition;
+
+        if self.registers.jump_condition(cc)? {
+            let stack_val = self.pop_stack_word(bus)?;
+            self.registers.pc.set_value(stack_val);
+
+            return Ok(5);
+        }
+
+        Ok(2)
+    }
+
+    pub(crate) fn reti<B: Bus>(&mut self, bus: &mut B) -> Result<u8, Error> {
+        let stack_val = self.pop_stack_word(bus)?;
+
+        self.ime = true;
+        self.registers.pc.set_value(stack_val);
+
+        Ok(4)
+    }
+
+    pub(crate) fn rst<B: Bus>(&mut self, operands: &Operands, bus: &mut B) -> Result<u8, Error> {
+        self.push_stack_word(*self.registers.pc.get_value(), bus)?;
+        self.registers.pc.set_value(operands.vector);
+        Ok(4)
     }
 }
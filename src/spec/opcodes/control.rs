@@ -1,57 +1,100 @@
-use crate::dasm::InstructionData;
-
+use crate::spec::bus::Bus;
 use crate::spec::cpu::{Error, CPU};
-use crate::spec::mmu::MMU;
-use crate::spec::mnemonic::Mnemonic;
-use crate::spec::opcode::Instruction;
-use crate::spec::opcodes::unexpected_op;
 use crate::spec::register::TRegister;
+use std::num::Wrapping;
+
+const SPEED_SWITCH_STALL: usize = 2050;
 
 impl CPU {
-    pub(crate) fn evaluate_control(
-        &mut self,
-        instruction_data: &InstructionData,
-        _opcode_data: &[u8; 2],
-        mmu: &mut MMU,
-    ) -> Result<u8, Error> {
-        match instruction_data.instruction {
-            Instruction::CCF => {
-                let mut flags = self.registers.flag_register();
-                flags.c = (flags.c == 0) as u8;
-                flags.h = 0;
-                flags.n = 0;
-
-                self.registers.f.set_value(flags.into());
-
-                Ok(1)
-            }
-            Instruction::SCF => {
-                let mut flags = self.registers.flag_register();
-                flags.c = 1;
-                flags.h = 0;
-                flags.n = 0;
+    pub(crate) fn ccf(&mut self) -> Result<u8, Error> {
+        let mut flags = self.registers.flag_register();
+        flags.c = (flags.c == 0) as u8;
+        flags.h = 0;
+        flags.n = 0;
 
-                self.registers.f.set_value(flags.into());
+        self.registers.f.set_value(flags.into());
 
-                Ok(1)
-            }
-            Instruction::NOP => Ok(1),
-            Instruction::HALT => {
+        Ok(1)
+    }
+
+    pub(crate) fn scf(&mut self) -> Result<u8, Error> {
+        let mut flags = self.registers.flag_register();
+        flags.c = 1;
+        flags.h = 0;
+        flags.n = 0;
+
+        self.registers.f.set_value(flags.into());
+
+        Ok(1)
+    }
+
+    pub(crate) fn nop(&mut self) -> Result<u8, Error> {
+        Ok(1)
+    }
+
+    pub(crate) fn halt<B: Bus>(&mut self, bus: &mut B) -> Result<u8, Error> {
+        if !self.ime && bus.interrupts_scheduled() {
+            // HALT bug: the CPU doesn't halt, and fails to increment PC
+            // when fetching the next opcode.
+            self.halt_bug = true;
+        } else {
+            self.halt = true;
+        }
+
+        Ok(1)
+    }
+
+    pub(crate) fn di(&mut self) -> Result<u8, Error> {
+        self.ime = false;
+        self.ei_delay = 0;
+        Ok(1)
+    }
+
+    pub(crate) fn ei(&mut self) -> Result<u8, Error> {
+        // IME is set after the instruction following EI
+        if !self.ime {
+            self.ei_delay = 2;
+        }
+        Ok(1)
+    }
+
+    /// STOP is encoded as `10 00`. What it does depends on whether a button is held,
+    /// an interrupt is pending, and a speed switch is armed in KEY1.
+    /// See https://gbdev.io/pandocs/Reducing_Power_Consumption.html#using-the-stop-instruction
+    pub(crate) fn stop<B: Bus>(&mut self, bus: &mut B) {
+        let interrupt_pending = bus.interrupts_scheduled();
+
+        if bus.input_asserted() {
+            if interrupt_pending {
+                self.execute_stop_operand();
+            } else {
                 self.halt = true;
-                Ok(1)
-            }
-            Instruction::STOP => {
-                unimplemented!()
             }
-            Instruction::DI => {
-                mmu.write_interrupt_enable_reg(false);
-                Ok(1)
-            }
-            Instruction::EI => {
-                mmu.write_interrupt_enable_reg(true);
-                Ok(1)
-            }
-            _ => Err(unexpected_op(&instruction_data.mnemonic, &Mnemonic::PUSH)),
+
+            return;
         }
+
+        bus.reset_div();
+
+        if bus.speed_switch_armed() {
+            bus.switch_speed();
+            self.speed_switch_stall = SPEED_SWITCH_STALL;
+
+            return;
+        }
+
+        if interrupt_pending {
+            self.execute_stop_operand();
+        }
+
+        self.stopped = true;
+    }
+
+    /// PC has already moved past both bytes of STOP. In some cases STOP behaves as a
+    /// 1-byte opcode and the second byte is executed next.
+    fn execute_stop_operand(&mut self) {
+        self.registers
+            .pc
+            .update_value_wrapped(|pc| pc - Wrapping(1));
     }
 }
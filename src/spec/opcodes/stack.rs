@@ -1,41 +1,33 @@
-use crate::dasm::InstructionData;
+use crate::dasm::Operands;
 
+use crate::spec::bus::Bus;
 use crate::spec::cpu::{Error, TStackable, CPU};
-use crate::spec::mmu::MMU;
-use crate::spec::mnemonic::Mnemonic;
-use crate::spec::opcode::Instruction;
-use crate::spec::opcodes::unexpected_op;
 
 impl CPU {
-    pub(crate) fn evaluate_stack_op(
+    pub(crate) fn push_rr<B: Bus>(
         &mut self,
-        instruction_data: &InstructionData,
-        _opcode_data: &[u8; 2],
-        mmu: &mut MMU,
+        operands: &Operands,
+        bus: &mut B,
     ) -> Result<u8, Error> {
-        match instruction_data.instruction {
-            Instruction::PUSH_RR => {
-                let qq = instruction_data.opcode_info.hi >> 1;
-                let value = self.registers.reg_pair_from_qq(qq)?.get_value();
+        let qq = operands.pair;
+        let value = self.registers.reg_pair_from_qq(qq)?.get_value();
 
-                self.push_stack_word(value, mmu)?;
+        self.push_stack_word(value, bus)?;
 
-                Ok(4)
-            }
-            Instruction::POP_RR => {
-                let qq = instruction_data.opcode_info.hi >> 1;
-                let mut value = self.pop_stack_word(mmu)?;
-                let mut reg_pair = self.registers.reg_pair_from_qq(qq)?;
-
-                if qq == 0b11 {
-                    value &= 0xFFF0;
-                }
+        Ok(4)
+    }
 
-                reg_pair.set_value_16(value);
+    pub(crate) fn pop_rr<B: Bus>(&mut self, operands: &Operands, bus: &mut B) -> Result<u8, Error> {
+        let qq = operands.pair;
+        let mut value = self.pop_stack_word(bus)?;
+        let mut reg_pair = self.registers.reg_pair_from_qq(qq)?;
 
-                Ok(3)
-            }
-            _ => Err(unexpected_op(&instruction_data.mnemonic, &Mnemonic::PUSH)),
+        if qq == 0b11 {
+            value &= 0xFFF0;
         }
+
+        reg_pair.set_value_16(value);
+
+        Ok(3)
     }
 }
@@ -0,0 +1,535 @@
+use crate::debug_logger::cpu_logger::CPU_LOGGER;
+use crate::spec::hardware_registers::io_address::{
+    BGP, LCDC, LY, LYC, OBP0, OBP1, SCX, SCY, STAT, WX, WY,
+};
+use crate::spec::hardware_registers::{Interrupt, IoHandler};
+use crate::spec::scheduler::{Event, Scheduler};
+
+pub const OAM_SIZE: usize = 0xA0;
+pub const VRAM_SIZE: usize = 0x2000;
+pub const SCREEN_WIDTH: usize = 160;
+pub const SCREEN_HEIGHT: usize = 144;
+
+const DOTS_PER_LINE: u64 = 456;
+const OAM_SCAN_DOTS: u64 = 80;
+const DRAWING_DOTS: u64 = 172;
+const HBLANK_DOTS: u64 = DOTS_PER_LINE - OAM_SCAN_DOTS - DRAWING_DOTS;
+const VISIBLE_LINES: u8 = 144;
+const LINES_PER_FRAME: u8 = 154;
+
+const LCDC_ENABLE: u8 = 0b1000_0000;
+const LCDC_WINDOW_MAP: u8 = 0b100_0000;
+const LCDC_WINDOW_ENABLE: u8 = 0b10_0000;
+const LCDC_TILE_DATA: u8 = 0b1_0000;
+const LCDC_BG_MAP: u8 = 0b1000;
+const LCDC_OBJ_SIZE: u8 = 0b100;
+const LCDC_OBJ_ENABLE: u8 = 0b10;
+const LCDC_BG_ENABLE: u8 = 0b1;
+const STAT_WRITE_MASK: u8 = 0b0111_1000;
+const STAT_LYC_INTERRUPT: u8 = 0b0100_0000;
+const STAT_LYC_EQUAL: u8 = 0b100;
+
+const OBJ_BEHIND_BG: u8 = 0b1000_0000;
+const OBJ_FLIP_Y: u8 = 0b100_0000;
+const OBJ_FLIP_X: u8 = 0b10_0000;
+const OBJ_PALETTE: u8 = 0b1_0000;
+const OBJS_PER_LINE: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mode {
+    HBlank,
+    VBlank,
+    OamScan,
+    Drawing,
+}
+
+impl Mode {
+    fn bits(&self) -> u8 {
+        match self {
+            Mode::HBlank => 0,
+            Mode::VBlank => 1,
+            Mode::OamScan => 2,
+            Mode::Drawing => 3,
+        }
+    }
+
+    fn stat_interrupt_select(&self) -> u8 {
+        match self {
+            Mode::HBlank => 0b1000,
+            Mode::VBlank => 0b1_0000,
+            Mode::OamScan => 0b10_0000,
+            Mode::Drawing => 0,
+        }
+    }
+}
+
+/// LCD timing and rendering.
+///
+/// Walks through the OAM scan, drawing and HBlank modes for each visible line and
+/// through VBlank, one scheduled event per mode change. Mode 3 is given a fixed
+/// length, and the whole line is rendered as it ends.
+#[derive(Hash)]
+pub struct Ppu {
+    lcdc: u8,
+    stat: u8,
+    ly: u8,
+    lyc: u8,
+    scy: u8,
+    scx: u8,
+    bgp: u8,
+    obp0: u8,
+    obp1: u8,
+    wy: u8,
+    wx: u8,
+    mode: Mode,
+    stat_line: bool,
+    frames: u64,
+    double_speed: bool,
+    window_line: u8,
+    pub(crate) oam: [u8; OAM_SIZE],
+    pub(crate) vram: Box<[u8]>,
+    framebuffer: Box<[u8]>,
+}
+
+impl Default for Ppu {
+    fn default() -> Self {
+        Ppu {
+            lcdc: 0x91,
+            stat: 0,
+            ly: 0,
+            lyc: 0,
+            scy: 0,
+            scx: 0,
+            bgp: 0xFC,
+            obp0: 0,
+            obp1: 0,
+            wy: 0,
+            wx: 0,
+            mode: Mode::OamScan,
+            stat_line: false,
+            frames: 0,
+            double_speed: false,
+            window_line: 0,
+            oam: [0; OAM_SIZE],
+            vram: Box::from([0; VRAM_SIZE]),
+            framebuffer: Box::from([0; SCREEN_WIDTH * SCREEN_HEIGHT]),
+        }
+    }
+}
+
+impl Ppu {
+    /// Registers the first mode change with the scheduler. The LCD is on when the
+    /// boot rom hands over control.
+    pub fn power_on(&mut self, scheduler: &mut Scheduler) {
+        scheduler.schedule_in(Event::PpuMode, self.t_cycles(OAM_SCAN_DOTS));
+    }
+
+    /// The scheduler counts CPU t-cycles, so in double speed mode each dot takes two.
+    pub fn set_double_speed(&mut self, double_speed: bool) {
+        self.double_speed = double_speed;
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn ly(&self) -> u8 {
+        self.ly
+    }
+
+    /// The number of frames that have entered VBlank since power on.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// The last frame drawn, one shade from 0 (lightest) to 3 (darkest) per pixel,
+    /// row by row. Blank while the LCD is off.
+    pub fn framebuffer(&self) -> &[u8] {
+        &self.framebuffer
+    }
+
+    pub fn read(&self, address: u16) -> u8 {
+        match address {
+            LCDC => self.lcdc,
+            STAT => 0x80 | self.stat | self.coincidence() | self.mode.bits(),
+            // GBDEBUG: gameboy-doctor logs expect LY to always read 0x90
+            LY if CPU_LOGGER.gb_doc() => 0x90,
+            LY => self.ly,
+            LYC => self.lyc,
+            SCY => self.scy,
+            SCX => self.scx,
+            BGP => self.bgp,
+            OBP0 => self.obp0,
+            OBP1 => self.obp1,
+            WY => self.wy,
+            WX => self.wx,
+            _ => unreachable!("PPU does not own address {:X}", address),
+        }
+    }
+
+    /// Returns the interrupt bits requested by the write.
+    pub fn write(&mut self, address: u16, value: u8, scheduler: &mut Scheduler) -> u8 {
+        match address {
+            LCDC => {
+                let was_enabled = self.enabled();
+                self.lcdc = value;
+
+                match (was_enabled, self.enabled()) {
+                    (true, false) => {
+                        self.ly = 0;
+                        self.mode = Mode::HBlank;
+                        self.stat_line = false;
+                        self.window_line = 0;
+                        self.framebuffer.fill(0);
+                        scheduler.cancel(Event::PpuMode);
+                    }
+                    (false, true) => {
+                        self.mode = Mode::OamScan;
+                        scheduler.schedule_in(Event::PpuMode, self.t_cycles(OAM_SCAN_DOTS));
+                    }
+                    _ => {}
+                }
+            }
+            STAT => self.stat = value & STAT_WRITE_MASK,
+            LY => {}
+            LYC => self.lyc = value,
+            SCY => self.scy = value,
+            SCX => self.scx = value,
+            BGP => self.bgp = value,
+            OBP0 => self.obp0 = value,
+            OBP1 => self.obp1 = value,
+            WY => self.wy = value,
+            WX => self.wx = value,
+            _ => unreachable!("PPU does not own address {:X}", address),
+        }
+
+        self.update_stat_line()
+    }
+
+    /// Handles `Event::PpuMode`, moving to the next mode and returning the interrupt
+    /// bits to request.
+    pub fn next_mode(&mut self, at: u64, scheduler: &mut Scheduler) -> u8 {
+        let mut interrupts = 0;
+
+        let (mode, duration) = match self.mode {
+            Mode::OamScan => (Mode::Drawing, DRAWING_DOTS),
+            Mode::Drawing => {
+                self.render_line();
+                (Mode::HBlank, HBLANK_DOTS)
+            }
+            Mode::HBlank => {
+                self.ly += 1;
+
+                if self.ly == VISIBLE_LINES {
+                    self.frames += 1;
+                    interrupts |= Interrupt::VBlank.get_position();
+                    (Mode::VBlank, DOTS_PER_LINE)
+                } else {
+                    (Mode::OamScan, OAM_SCAN_DOTS)
+                }
+            }
+            Mode::VBlank => {
+                self.ly += 1;
+
+                if self.ly == LINES_PER_FRAME {
+                    self.ly = 0;
+                    self.window_line = 0;
+                    (Mode::OamScan, OAM_SCAN_DOTS)
+                } else {
+                    (Mode::VBlank, DOTS_PER_LINE)
+                }
+            }
+        };
+
+        self.mode = mode;
+        scheduler.schedule(Event::PpuMode, at + self.t_cycles(duration));
+
+        interrupts | self.update_stat_line()
+    }
+
+    fn render_line(&mut self) {
+        let mut bg_colors = [0; SCREEN_WIDTH];
+
+        if self.lcdc & LCDC_BG_ENABLE != 0 {
+            self.render_background(&mut bg_colors);
+        }
+
+        let start = self.ly as usize * SCREEN_WIDTH;
+        let line = &mut self.framebuffer[start..start + SCREEN_WIDTH];
+
+        for (pixel, &color) in line.iter_mut().zip(bg_colors.iter()) {
+            *pixel = shade(self.bgp, color);
+        }
+
+        if self.lcdc & LCDC_OBJ_ENABLE != 0 {
+            self.render_objects(&bg_colors);
+        }
+    }
+
+    /// Fills in the background and window color indices for the current line.
+    fn render_background(&mut self, colors: &mut [u8; SCREEN_WIDTH]) {
+        let bg_map = if self.lcdc & LCDC_BG_MAP != 0 {
+            0x1C00
+        } else {
+            0x1800
+        };
+        let y = self.ly.wrapping_add(self.scy);
+
+        for (x, color) in colors.iter_mut().enumerate() {
+            *color = self.tile_map_color(bg_map, (x as u8).wrapping_add(self.scx), y);
+        }
+
+        let window_x = self.wx as i16 - 7;
+
+        if self.lcdc & LCDC_WINDOW_ENABLE == 0
+            || self.ly < self.wy
+            || window_x >= SCREEN_WIDTH as i16
+        {
+            return;
+        }
+
+        let window_map = if self.lcdc & LCDC_WINDOW_MAP != 0 {
+            0x1C00
+        } else {
+            0x1800
+        };
+
+        let start = window_x.max(0) as usize;
+
+        for (x, color) in colors.iter_mut().enumerate().skip(start) {
+            let column = (x as i16 - window_x) as u8;
+            *color = self.tile_map_color(window_map, column, self.window_line);
+        }
+
+        self.window_line += 1;
+    }
+
+    /// The color index at (`x`, `y`) of the 256x256 map at `map` in VRAM.
+    fn tile_map_color(&self, map: usize, x: u8, y: u8) -> u8 {
+        let tile = self.vram[map + (y as usize / 8) * 32 + x as usize / 8];
+
+        let tile_address = if self.lcdc & LCDC_TILE_DATA != 0 {
+            tile as usize * 16
+        } else {
+            (0x1000 + (tile as i8 as isize) * 16) as usize
+        };
+
+        self.tile_color(tile_address, x % 8, y % 8)
+    }
+
+    fn tile_color(&self, tile_address: usize, x: u8, y: u8) -> u8 {
+        let low = self.vram[tile_address + y as usize * 2];
+        let high = self.vram[tile_address + y as usize * 2 + 1];
+        let bit = 7 - x;
+
+        ((high >> bit) & 1) << 1 | ((low >> bit) & 1)
+    }
+
+    fn render_objects(&mut self, bg_colors: &[u8; SCREEN_WIDTH]) {
+        let height = if self.lcdc & LCDC_OBJ_SIZE != 0 {
+            16
+        } else {
+            8
+        };
+        let ly = self.ly as i16;
+
+        // The first 10 objects in OAM on this line are drawn. Where they overlap,
+        // the one furthest left wins, then the one first in OAM.
+        let mut objects: Vec<&[u8]> = self
+            .oam
+            .chunks_exact(4)
+            .filter(|object| {
+                let top = object[0] as i16 - 16;
+                (top..top + height).contains(&ly)
+            })
+            .take(OBJS_PER_LINE)
+            .collect();
+        objects.sort_by_key(|object| object[1]);
+
+        let start = self.ly as usize * SCREEN_WIDTH;
+        let mut drawn = [false; SCREEN_WIDTH];
+
+        for object in objects {
+            let (y, x, attributes) = (object[0] as i16 - 16, object[1] as i16 - 8, object[3]);
+
+            let mut row = (ly - y) as u8;
+            if attributes & OBJ_FLIP_Y != 0 {
+                row = height as u8 - 1 - row;
+            }
+
+            let tile = if height == 16 {
+                object[2] & 0xFE
+            } else {
+                object[2]
+            };
+            let tile_address = tile as usize * 16 + (row as usize / 8) * 16;
+            let palette = if attributes & OBJ_PALETTE != 0 {
+                self.obp1
+            } else {
+                self.obp0
+            };
+
+            for column in 0..8 {
+                let screen_x = x + column;
+
+                if !(0..SCREEN_WIDTH as i16).contains(&screen_x) || drawn[screen_x as usize] {
+                    continue;
+                }
+
+                let pixel = if attributes & OBJ_FLIP_X != 0 {
+                    7 - column
+                } else {
+                    column
+                };
+                let color = self.tile_color(tile_address, pixel as u8, row % 8);
+
+                if color == 0 {
+                    continue;
+                }
+
+                let screen_x = screen_x as usize;
+                drawn[screen_x] = true;
+
+                if attributes & OBJ_BEHIND_BG == 0 || bg_colors[screen_x] == 0 {
+                    self.framebuffer[start + screen_x] = shade(palette, color);
+                }
+            }
+        }
+    }
+
+    fn t_cycles(&self, dots: u64) -> u64 {
+        if self.double_speed {
+            dots * 2
+        } else {
+            dots
+        }
+    }
+
+    /// T-cycles in one frame at the current speed.
+    pub fn frame_t_cycles(&self) -> u64 {
+        self.t_cycles(DOTS_PER_LINE * LINES_PER_FRAME as u64)
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.lcdc & LCDC_ENABLE != 0
+    }
+
+    fn coincidence(&self) -> u8 {
+        if self.enabled() && self.ly == self.lyc {
+            STAT_LYC_EQUAL
+        } else {
+            0
+        }
+    }
+
+    /// The STAT interrupt is requested on the rising edge of the OR of all enabled sources.
+    fn update_stat_line(&mut self) -> u8 {
+        let lyc_source = self.coincidence() != 0 && (self.stat & STAT_LYC_INTERRUPT) != 0;
+        let mode_source = self.enabled() && (self.stat & self.mode.stat_interrupt_select()) != 0;
+        let line = lyc_source || mode_source;
+        let rising_edge = line && !self.stat_line;
+
+        self.stat_line = line;
+
+        if rising_edge {
+            Interrupt::LCDStat.get_position()
+        } else {
+            0
+        }
+    }
+}
+
+/// Maps a color index through a DMG palette register.
+fn shade(palette: u8, color: u8) -> u8 {
+    (palette >> (color * 2)) & 0b11
+}
+
+impl IoHandler for Ppu {
+    fn read_register(&self, address: u16, _scheduler: &Scheduler) -> u8 {
+        self.read(address)
+    }
+
+    fn write_register(&mut self, address: u16, value: u8, scheduler: &mut Scheduler) -> u8 {
+        self.write(address, value, scheduler)
+    }
+}
+
+#[cfg(test)]
+mod ppu_test {
+    use crate::spec::ppu::{Ppu, SCREEN_WIDTH};
+
+    /// A PPU with tile 1 filled with color 3 and tile 2 with color 1.
+    fn ppu_with_tiles() -> Ppu {
+        let mut ppu = Ppu {
+            bgp: 0xE4,
+            obp0: 0xE4,
+            ..Ppu::default()
+        };
+
+        ppu.vram[0x10..0x20].fill(0xFF);
+        for row in ppu.vram[0x20..0x30].chunks_exact_mut(2) {
+            row[0] = 0xFF;
+        }
+
+        ppu
+    }
+
+    fn line(ppu: &Ppu) -> &[u8] {
+        let start = ppu.ly as usize * SCREEN_WIDTH;
+        &ppu.framebuffer()[start..start + SCREEN_WIDTH]
+    }
+
+    #[test]
+    fn background_is_drawn_through_the_tile_map_and_scroll() {
+        let mut ppu = ppu_with_tiles();
+        ppu.vram[0x1800] = 1;
+        ppu.scx = 4;
+        ppu.ly = 3;
+
+        ppu.render_line();
+
+        assert_eq!(line(&ppu)[..5], [3, 3, 3, 3, 0]);
+    }
+
+    #[test]
+    fn window_covers_the_background() {
+        let mut ppu = ppu_with_tiles();
+        ppu.lcdc |= 0b110_0000;
+        ppu.vram[0x1C00] = 2;
+        ppu.wx = 7 + 156;
+
+        ppu.render_line();
+
+        assert_eq!(line(&ppu)[155..], [0, 1, 1, 1, 1]);
+        assert_eq!(ppu.window_line, 1);
+    }
+
+    #[test]
+    fn objects_are_drawn_over_the_background_unless_behind_it() {
+        let mut ppu = ppu_with_tiles();
+        ppu.lcdc |= 0b10;
+        ppu.vram[0x1800] = 2;
+        ppu.oam[..8].copy_from_slice(&[16, 12, 1, 0b1000_0000, 16, 20, 1, 0]);
+
+        ppu.render_line();
+
+        // The first object is behind the background, so only shows where it's color 0
+        assert_eq!(line(&ppu)[4..12], [1, 1, 1, 1, 3, 3, 3, 3]);
+        assert_eq!(line(&ppu)[12..21], [3, 3, 3, 3, 3, 3, 3, 3, 0]);
+    }
+
+    #[test]
+    fn only_ten_objects_are_drawn_per_line() {
+        let mut ppu = ppu_with_tiles();
+        ppu.lcdc |= 0b10;
+
+        for (index, object) in ppu.oam.chunks_exact_mut(4).take(11).enumerate() {
+            object.copy_from_slice(&[16, 8 + index as u8 * 8, 1, 0]);
+        }
+
+        ppu.render_line();
+
+        assert_eq!(line(&ppu)[79], 3);
+        assert_eq!(line(&ppu)[80], 0);
+    }
+}
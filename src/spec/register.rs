@@ -36,7 +36,7 @@ pub trait TRegister<'a> {
     fn set_value(&mut self, value: Self::ValueType);
 }
 
-#[derive(Debug)]
+#[derive(Debug, Hash)]
 pub struct Register<T: Default> {
     value: T,
     tag: RegisterType,
@@ -78,7 +78,7 @@ impl<T: Default> Register<T> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Hash)]
 pub enum RegisterType {
     A,
     B,
@@ -179,7 +179,7 @@ impl<'a> RegisterPair<'a> {
 }
 
 /// TODO: Implement Display Trait for Registers
-#[derive(Debug)]
+#[derive(Debug, Hash)]
 pub struct Registers {
     pub a: Register<u8>,
     pub b: Register<u8>,
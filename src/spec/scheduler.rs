@@ -0,0 +1,193 @@
+/// Events that components can register with the scheduler.
+///
+/// Each event can be pending at most once, so rescheduling an event replaces
+/// its previous timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    TimerOverflow,
+    PpuMode,
+    DmaComplete,
+    SerialTransfer,
+    FrameSequencer,
+    /// An attached device needs ticking.
+    Device,
+}
+
+const EVENT_COUNT: usize = 6;
+const IDLE: u64 = u64::MAX;
+
+impl Event {
+    const ALL: [Event; EVENT_COUNT] = [
+        Event::TimerOverflow,
+        Event::PpuMode,
+        Event::DmaComplete,
+        Event::SerialTransfer,
+        Event::FrameSequencer,
+        Event::Device,
+    ];
+
+    fn index(&self) -> usize {
+        match self {
+            Event::TimerOverflow => 0,
+            Event::PpuMode => 1,
+            Event::DmaComplete => 2,
+            Event::SerialTransfer => 3,
+            Event::FrameSequencer => 4,
+            Event::Device => 5,
+        }
+    }
+}
+
+/// Cycle-timestamped event queue.
+///
+/// Timestamps are absolute t-cycles since power on. Components schedule the next
+/// point in time at which they need to do work, and the owner of the scheduler
+/// pops due events after advancing time.
+#[derive(Hash)]
+pub struct Scheduler {
+    now: u64,
+    pending: [u64; EVENT_COUNT],
+    next: u64,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Scheduler {
+            now: 0,
+            pending: [IDLE; EVENT_COUNT],
+            next: IDLE,
+        }
+    }
+}
+
+impl Scheduler {
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    pub fn advance(&mut self, t_cycles: u64) {
+        self.now += t_cycles;
+    }
+
+    /// Lets `t_cycles` pass without anything happening, moving every pending event
+    /// back by as long.
+    pub fn postpone(&mut self, t_cycles: u64) {
+        self.now += t_cycles;
+
+        for at in self.pending.iter_mut().filter(|at| **at != IDLE) {
+            *at += t_cycles;
+        }
+
+        self.recompute_next();
+    }
+
+    pub fn schedule(&mut self, event: Event, at: u64) {
+        let previous = std::mem::replace(&mut self.pending[event.index()], at);
+
+        if previous == self.next {
+            self.recompute_next();
+        } else {
+            self.next = self.next.min(at);
+        }
+    }
+
+    pub fn schedule_in(&mut self, event: Event, t_cycles: u64) {
+        self.schedule(event, self.now + t_cycles)
+    }
+
+    pub fn cancel(&mut self, event: Event) {
+        let at = std::mem::replace(&mut self.pending[event.index()], IDLE);
+
+        if at == self.next {
+            self.recompute_next();
+        }
+    }
+
+    pub fn is_pending(&self, event: Event) -> bool {
+        self.pending[event.index()] != IDLE
+    }
+
+    /// The timestamp of the earliest pending event, if any.
+    pub fn next_event_at(&self) -> Option<u64> {
+        if self.next == IDLE {
+            None
+        } else {
+            Some(self.next)
+        }
+    }
+
+    /// The number of t-cycles until the earliest pending event is due.
+    pub fn cycles_until_next_event(&self) -> Option<u64> {
+        self.next_event_at().map(|at| at.saturating_sub(self.now))
+    }
+
+    /// Removes and returns the earliest event that is due at or before the current
+    /// time, along with the timestamp it was scheduled for.
+    pub fn pop_due(&mut self) -> Option<(Event, u64)> {
+        if self.next > self.now {
+            return None;
+        }
+
+        let event = Event::ALL
+            .iter()
+            .copied()
+            .min_by_key(|event| self.pending[event.index()])?;
+        let at = std::mem::replace(&mut self.pending[event.index()], IDLE);
+
+        self.recompute_next();
+
+        Some((event, at))
+    }
+
+    fn recompute_next(&mut self) {
+        self.next = self.pending.iter().copied().min().unwrap_or(IDLE);
+    }
+}
+
+#[cfg(test)]
+mod scheduler_test {
+    use crate::spec::scheduler::{Event, Scheduler};
+
+    #[test]
+    fn pops_events_in_timestamp_order() {
+        let mut scheduler = Scheduler::default();
+
+        scheduler.schedule(Event::PpuMode, 80);
+        scheduler.schedule(Event::TimerOverflow, 16);
+        scheduler.advance(100);
+
+        assert_eq!(scheduler.pop_due(), Some((Event::TimerOverflow, 16)));
+        assert_eq!(scheduler.pop_due(), Some((Event::PpuMode, 80)));
+        assert_eq!(scheduler.pop_due(), None);
+    }
+
+    #[test]
+    fn events_are_not_due_early() {
+        let mut scheduler = Scheduler::default();
+
+        scheduler.schedule_in(Event::DmaComplete, 640);
+        scheduler.advance(639);
+
+        assert_eq!(scheduler.pop_due(), None);
+        assert_eq!(scheduler.cycles_until_next_event(), Some(1));
+
+        scheduler.advance(1);
+
+        assert_eq!(scheduler.pop_due(), Some((Event::DmaComplete, 640)));
+    }
+
+    #[test]
+    fn rescheduling_replaces_the_pending_event() {
+        let mut scheduler = Scheduler::default();
+
+        scheduler.schedule(Event::TimerOverflow, 10);
+        scheduler.schedule(Event::TimerOverflow, 50);
+        scheduler.advance(20);
+
+        assert_eq!(scheduler.pop_due(), None);
+
+        scheduler.cancel(Event::TimerOverflow);
+
+        assert_eq!(scheduler.next_event_at(), None);
+    }
+}
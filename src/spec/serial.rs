@@ -0,0 +1,127 @@
+use crate::spec::hardware_registers::io_address::{SB, SC};
+use crate::spec::hardware_registers::{Interrupt, IoHandler};
+use crate::spec::scheduler::{Event, Scheduler};
+
+const TRANSFER_START: u8 = 0b1000_0000;
+const INTERNAL_CLOCK: u8 = 0b1;
+
+/// The internal clock runs at 8192Hz, one bit every 512 t-cycles.
+const TRANSFER_CYCLES: u64 = 512 * 8;
+
+/// SB and SC.
+///
+/// Writing SC with bit 7 set starts a transfer. On the internal clock the
+/// transfer finishes 8 bits later, at which point SB holds the byte received
+/// over the link cable. On an external clock it waits for the other end.
+/// Either way the serial interrupt is requested when it completes.
+///
+/// Whatever is on the other end of the cable is a `Device`, connected through
+/// the MMU's `DeviceBus`.
+#[derive(Default, Hash)]
+pub struct Serial {
+    sb: u8,
+    sc: u8,
+    sent: Option<u8>,
+}
+
+impl Serial {
+    /// The byte that will be shifted out.
+    pub fn outgoing(&self) -> u8 {
+        self.sb
+    }
+
+    pub fn waiting_for_external_clock(&self) -> bool {
+        self.sc & (TRANSFER_START | INTERNAL_CLOCK) == TRANSFER_START
+    }
+
+    /// The last byte shifted out, if a transfer has completed since this was last
+    /// called.
+    pub fn take_sent(&mut self) -> Option<u8> {
+        self.sent.take()
+    }
+
+    /// Finishes the transfer in progress with the byte shifted in, returning the
+    /// interrupt bits to request.
+    pub fn complete_transfer(&mut self, incoming: u8) -> u8 {
+        self.sent = Some(self.sb);
+        self.sb = incoming;
+        self.sc &= !TRANSFER_START;
+
+        Interrupt::Serial.get_position()
+    }
+}
+
+impl IoHandler for Serial {
+    fn read_register(&self, address: u16, _scheduler: &Scheduler) -> u8 {
+        match address {
+            SB => self.sb,
+            SC => self.sc,
+            _ => unreachable!("Serial does not own address {:X}", address),
+        }
+    }
+
+    fn write_register(&mut self, address: u16, value: u8, scheduler: &mut Scheduler) -> u8 {
+        match address {
+            SB => self.sb = value,
+            SC => {
+                self.sc = value;
+
+                if value & (TRANSFER_START | INTERNAL_CLOCK) == TRANSFER_START | INTERNAL_CLOCK {
+                    scheduler.schedule_in(Event::SerialTransfer, TRANSFER_CYCLES);
+                } else {
+                    scheduler.cancel(Event::SerialTransfer);
+                }
+            }
+            _ => unreachable!("Serial does not own address {:X}", address),
+        }
+
+        0
+    }
+}
+
+#[cfg(test)]
+mod serial_test {
+    use crate::spec::hardware_registers::io_address::{SB, SC};
+    use crate::spec::hardware_registers::IoHandler;
+    use crate::spec::scheduler::{Event, Scheduler};
+    use crate::spec::serial::Serial;
+
+    fn start_transfer(serial: &mut Serial, scheduler: &mut Scheduler, byte: u8, sc: u8) {
+        serial.write_register(SB, byte, scheduler);
+        serial.write_register(SC, sc, scheduler);
+    }
+
+    #[test]
+    fn internal_clock_transfer_takes_eight_bits() {
+        let mut scheduler = Scheduler::default();
+        let mut serial = Serial::default();
+
+        start_transfer(&mut serial, &mut scheduler, 0x42, 0x81);
+
+        assert_eq!(scheduler.next_event_at(), Some(4096));
+        assert_eq!(serial.outgoing(), 0x42);
+
+        scheduler.advance(4096);
+        assert_eq!(scheduler.pop_due(), Some((Event::SerialTransfer, 4096)));
+        assert_eq!(serial.complete_transfer(0x99), 0b1000);
+        assert_eq!(serial.read_register(SB, &scheduler), 0x99);
+        assert_eq!(serial.read_register(SC, &scheduler), 0x01);
+        assert_eq!(serial.take_sent(), Some(0x42));
+        assert_eq!(serial.take_sent(), None);
+    }
+
+    #[test]
+    fn external_clock_waits_for_the_other_end() {
+        let mut scheduler = Scheduler::default();
+        let mut serial = Serial::default();
+
+        start_transfer(&mut serial, &mut scheduler, 0x42, 0x80);
+
+        assert_eq!(scheduler.next_event_at(), None);
+        assert!(serial.waiting_for_external_clock());
+        assert_eq!(serial.read_register(SC, &scheduler), 0x80);
+
+        serial.complete_transfer(0x99);
+        assert!(!serial.waiting_for_external_clock());
+    }
+}
@@ -0,0 +1,326 @@
+use crate::spec::hardware_registers::io_address::{DIV, TAC, TIMA, TMA};
+use crate::spec::hardware_registers::{Interrupt, IoHandler};
+use crate::spec::scheduler::{Event, Scheduler};
+
+/// The value of the internal system counter when the boot rom hands over control.
+const POST_BOOT_SYSTEM_COUNTER: u64 = 0xABCC;
+
+/// The system counter bit whose falling edge clocks the APU frame sequencer at
+/// 512Hz. Double speed doubles the counter's rate, so the bit above is used.
+const FRAME_SEQUENCER_BIT: u64 = 1 << 12;
+const DOUBLE_SPEED_FRAME_SEQUENCER_BIT: u64 = 1 << 13;
+
+#[derive(Debug)]
+pub struct TimerControl {
+    enabled: bool,
+    clock_select: u64,
+}
+
+impl From<u8> for TimerControl {
+    fn from(value: u8) -> Self {
+        let enabled = ((value & 0b100) >> 2) == 1;
+        let clock_select = match value & 0b011 {
+            0b00 => 1024,
+            0b01 => 16,
+            0b10 => 64,
+            0b11 => 256,
+            _ => unreachable!(),
+        };
+
+        Self {
+            enabled,
+            clock_select,
+        }
+    }
+}
+
+/// DIV, TIMA, TMA and TAC.
+///
+/// Nothing here is polled. DIV and TIMA are derived from the scheduler timestamp
+/// when they're read, and the events registered are the next TIMA overflow and
+/// the next falling edge of the bit that clocks the APU frame sequencer.
+///
+/// The internal system counter is `(t + counter_bias) mod 0x10000` for a timestamp `t`,
+/// and TIMA increments whenever that counter crosses a multiple of the selected
+/// clock period.
+#[derive(Hash)]
+pub struct Timer {
+    counter_bias: u64,
+    tima: u8,
+    tima_synced_at: u64,
+    tma: u8,
+    tac: u8,
+    double_speed: bool,
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Timer {
+            counter_bias: POST_BOOT_SYSTEM_COUNTER,
+            tima: 0,
+            tima_synced_at: 0,
+            tma: 0,
+            tac: 0,
+            double_speed: false,
+        }
+    }
+}
+
+impl Timer {
+    pub fn read(&self, address: u16, now: u64) -> u8 {
+        match address {
+            DIV => (self.system_counter(now) >> 8) as u8,
+            TIMA => self
+                .tima
+                .wrapping_add(self.ticks_between(self.tima_synced_at, now) as u8),
+            TMA => self.tma,
+            TAC => self.tac,
+            _ => unreachable!("Timer does not own address {:X}", address),
+        }
+    }
+
+    /// Returns true if the write caused TIMA to overflow.
+    pub fn write(&mut self, address: u16, value: u8, scheduler: &mut Scheduler) -> bool {
+        let now = scheduler.now();
+        self.sync(now);
+
+        let overflowed = match address {
+            DIV => {
+                // Resetting the system counter can produce a falling edge on the selected
+                // bit, and on the frame sequencer's
+                let falling_edge = self.selected_bit_high(now);
+                let frame_sequencer_edge =
+                    self.system_counter(now) & self.frame_sequencer_bit() != 0;
+                self.counter_bias = (0x10000 - (now % 0x10000)) % 0x10000;
+
+                if frame_sequencer_edge {
+                    scheduler.schedule(Event::FrameSequencer, now);
+                } else {
+                    self.schedule_frame_sequencer(scheduler);
+                }
+
+                falling_edge && self.increment()
+            }
+            TIMA => {
+                self.tima = value;
+                false
+            }
+            TMA => {
+                self.tma = value;
+                false
+            }
+            TAC => {
+                let was_high = self.selected_bit_high(now);
+                self.tac = value;
+
+                was_high && !self.selected_bit_high(now) && self.increment()
+            }
+            _ => unreachable!("Timer does not own address {:X}", address),
+        };
+
+        self.reschedule(scheduler);
+
+        overflowed
+    }
+
+    /// Handles `Event::TimerOverflow`, reloading TIMA from TMA and returning the
+    /// interrupt bits to request.
+    pub fn overflow(&mut self, at: u64, scheduler: &mut Scheduler) -> u8 {
+        self.tima = self.tma;
+        self.tima_synced_at = at;
+        self.reschedule(scheduler);
+
+        Interrupt::Timer.get_position()
+    }
+
+    /// Holds the system counter and TIMA where they are while `t_cycles` pass, as
+    /// happens during STOP. The scheduler's events are postponed separately.
+    pub fn postpone(&mut self, t_cycles: u64) {
+        self.counter_bias = (self.counter_bias + 0x10000 - t_cycles % 0x10000) % 0x10000;
+        self.tima_synced_at += t_cycles;
+    }
+
+    /// Schedules `Event::FrameSequencer` for the next falling edge of its bit.
+    pub fn schedule_frame_sequencer(&self, scheduler: &mut Scheduler) {
+        let period = self.frame_sequencer_bit() << 1;
+        let counter = scheduler.now() + self.counter_bias;
+
+        scheduler.schedule(
+            Event::FrameSequencer,
+            (counter / period + 1) * period - self.counter_bias,
+        );
+    }
+
+    /// The frame sequencer's bit depends on the speed mode.
+    pub fn set_double_speed(&mut self, double_speed: bool, scheduler: &mut Scheduler) {
+        self.double_speed = double_speed;
+        self.schedule_frame_sequencer(scheduler);
+    }
+
+    fn frame_sequencer_bit(&self) -> u64 {
+        if self.double_speed {
+            DOUBLE_SPEED_FRAME_SEQUENCER_BIT
+        } else {
+            FRAME_SEQUENCER_BIT
+        }
+    }
+
+    fn control(&self) -> TimerControl {
+        TimerControl::from(self.tac)
+    }
+
+    fn system_counter(&self, at: u64) -> u64 {
+        (at + self.counter_bias) % 0x10000
+    }
+
+    fn selected_bit_high(&self, at: u64) -> bool {
+        let control = self.control();
+
+        control.enabled && (self.system_counter(at) & (control.clock_select >> 1)) != 0
+    }
+
+    fn ticks_between(&self, from: u64, to: u64) -> u64 {
+        let control = self.control();
+
+        if !control.enabled {
+            return 0;
+        }
+
+        (to + self.counter_bias) / control.clock_select
+            - (from + self.counter_bias) / control.clock_select
+    }
+
+    fn sync(&mut self, now: u64) {
+        self.tima = self
+            .tima
+            .wrapping_add(self.ticks_between(self.tima_synced_at, now) as u8);
+        self.tima_synced_at = now;
+    }
+
+    fn increment(&mut self) -> bool {
+        match self.tima.checked_add(1) {
+            Some(next_tima) => {
+                self.tima = next_tima;
+                false
+            }
+            None => {
+                self.tima = self.tma;
+                true
+            }
+        }
+    }
+
+    fn reschedule(&self, scheduler: &mut Scheduler) {
+        let control = self.control();
+
+        if !control.enabled {
+            scheduler.cancel(Event::TimerOverflow);
+            return;
+        }
+
+        let period = control.clock_select;
+        let ticks_until_overflow = 0x100 - self.tima as u64;
+        let next_tick_index = (self.tima_synced_at + self.counter_bias) / period;
+        let overflow_at = (next_tick_index + ticks_until_overflow) * period - self.counter_bias;
+
+        scheduler.schedule(Event::TimerOverflow, overflow_at);
+    }
+}
+
+impl IoHandler for Timer {
+    fn read_register(&self, address: u16, scheduler: &Scheduler) -> u8 {
+        self.read(address, scheduler.now())
+    }
+
+    fn write_register(&mut self, address: u16, value: u8, scheduler: &mut Scheduler) -> u8 {
+        if self.write(address, value, scheduler) {
+            Interrupt::Timer.get_position()
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod timer_test {
+    use crate::spec::hardware_registers::io_address::{DIV, TAC, TIMA, TMA};
+    use crate::spec::scheduler::{Event, Scheduler};
+    use crate::spec::timer::Timer;
+
+    fn enabled_timer(scheduler: &mut Scheduler) -> Timer {
+        let mut timer = Timer::default();
+
+        timer.write(DIV, 0, scheduler);
+        // 16 t-cycle period
+        timer.write(TAC, 0b101, scheduler);
+
+        timer
+    }
+
+    #[test]
+    fn div_counts_every_256_cycles() {
+        let mut scheduler = Scheduler::default();
+        let mut timer = Timer::default();
+
+        timer.write(DIV, 0, &mut scheduler);
+        scheduler.advance(255);
+        assert_eq!(timer.read(DIV, scheduler.now()), 0);
+
+        scheduler.advance(1);
+        assert_eq!(timer.read(DIV, scheduler.now()), 1);
+    }
+
+    #[test]
+    fn tima_counts_at_selected_frequency() {
+        let mut scheduler = Scheduler::default();
+        let timer = enabled_timer(&mut scheduler);
+
+        scheduler.advance(16 * 10 + 15);
+
+        assert_eq!(timer.read(TIMA, scheduler.now()), 10);
+    }
+
+    #[test]
+    fn overflow_is_scheduled_and_reloads_tma() {
+        let mut scheduler = Scheduler::default();
+        let mut timer = enabled_timer(&mut scheduler);
+
+        timer.write(TMA, 0xF0, &mut scheduler);
+        timer.write(TIMA, 0xFE, &mut scheduler);
+
+        assert_eq!(scheduler.next_event_at(), Some(32));
+
+        scheduler.advance(32);
+        let (event, at) = scheduler.pop_due().unwrap();
+        assert_eq!(event, Event::TimerOverflow);
+        assert_eq!(timer.overflow(at, &mut scheduler), 0b100);
+        assert_eq!(timer.read(TIMA, scheduler.now()), 0xF0);
+
+        scheduler.advance(16);
+        assert_eq!(timer.read(TIMA, scheduler.now()), 0xF1);
+    }
+
+    #[test]
+    fn div_reset_on_falling_edge_increments_tima() {
+        let mut scheduler = Scheduler::default();
+        let mut timer = enabled_timer(&mut scheduler);
+
+        // Bit 3 of the system counter is high
+        scheduler.advance(8);
+        timer.write(DIV, 0, &mut scheduler);
+
+        assert_eq!(timer.read(TIMA, scheduler.now()), 1);
+    }
+
+    #[test]
+    fn disabled_timer_does_not_count() {
+        let mut scheduler = Scheduler::default();
+        let mut timer = Timer::default();
+
+        timer.write(TAC, 0b001, &mut scheduler);
+        scheduler.advance(1024);
+
+        assert_eq!(timer.read(TIMA, scheduler.now()), 0);
+        assert_eq!(scheduler.next_event_at(), None);
+    }
+}
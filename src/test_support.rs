@@ -0,0 +1,183 @@
+use crate::link::capture::ByteCapture;
+use crate::spec::gameboy::{GameBoy, StopReason};
+use crate::spec::ppu::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::spec::register::TRegister;
+use crate::util::hash::fnv1a;
+use crate::util::image::{save_png, Palette};
+use std::fs::{self, File};
+use std::path::Path;
+use std::time::Instant;
+
+/// What a screenshot test's framebuffer should look like.
+pub enum Reference {
+    /// The `fnv1a` hash of the framebuffer's shades.
+    Hash(u64),
+    /// A PNG in `tests/fixtures/screenshots`, in the grayscale palette.
+    Image(&'static str),
+}
+
+/// Registers mooneye test ROMs load before `LD B,B` when they pass. Failures
+/// load 0x42 into every one of them instead.
+const FIBONACCI: [u8; 6] = [3, 5, 8, 13, 21, 34];
+
+/// A 32KB ROM with `program` at the entry point.
+pub fn rom_with_program(program: &[u8]) -> Vec<u8> {
+    let mut rom = vec![0; 0x8000];
+    rom[0x100..0x100 + program.len()].copy_from_slice(program);
+
+    rom
+}
+
+pub fn run_integration_test(fixture_name: &str) -> Result<(), String> {
+    let fixture_location = format!("./tests/fixtures/{}", fixture_name);
+    let rom = fs::read(&fixture_location)
+        .map_err(|_| format!("Failed to read fixture from location: {}", fixture_location))?;
+
+    let serial_port_out = ByteCapture::default();
+    let mut gameboy =
+        GameBoy::new(&rom).map_err(|e| format!("Failed to initialize gameboy with {:?}", e))?;
+
+    gameboy.connect_link_cable(Box::new(serial_port_out.clone()));
+    let mut cycles = 0;
+    let mut received = 0;
+    let now = Instant::now();
+    loop {
+        cycles += gameboy
+            .cycle()
+            .map_err(|e| format!("Failed to execute gameboy cycle with error {:?}", e))?;
+
+        if serial_port_out.len() == received {
+            continue;
+        }
+
+        received = serial_port_out.len();
+        let output = serial_port_out.output();
+
+        if output.contains("Passed") {
+            break;
+        }
+
+        if output.to_lowercase().contains("failed") {
+            return Err(format!("{} received fail code from ROM", fixture_name));
+        }
+    }
+
+    let t_cycles = cycles * 4;
+    let expected_seconds = t_cycles as f64 / 4194304.0;
+    let actual_seconds = now.elapsed().as_secs();
+    assert!(actual_seconds as f64 <= expected_seconds);
+    println!(
+        "{} took {} cycles to complete. Expected time {} sec. Actual time to complete: {} sec",
+        fixture_name, t_cycles, expected_seconds, actual_seconds
+    );
+
+    Ok(())
+}
+
+fn read_fixture(fixture_name: &str) -> Result<Vec<u8>, String> {
+    let fixture_location = format!("./tests/fixtures/{}", fixture_name);
+
+    fs::read(&fixture_location)
+        .map_err(|_| format!("Failed to read fixture from location: {}", fixture_location))
+}
+
+fn load(rom: &[u8]) -> Result<GameBoy, String> {
+    GameBoy::new(rom).map_err(|e| format!("Failed to initialize gameboy with {:?}", e))
+}
+
+/// Runs until the ROM executes `LD B,B` and checks for the Fibonacci sequence in
+/// B, C, D, E, H and L.
+pub fn run_breakpoint_test(fixture_name: &str, max_frames: u64) -> Result<(), String> {
+    run_breakpoint_test_rom(fixture_name, &read_fixture(fixture_name)?, max_frames)
+}
+
+pub fn run_breakpoint_test_rom(name: &str, rom: &[u8], max_frames: u64) -> Result<(), String> {
+    let mut gameboy = load(rom)?;
+    gameboy.set_software_breakpoints(true);
+
+    let result = gameboy.run_until(|gameboy| gameboy.frames() >= max_frames);
+
+    match result.stop {
+        StopReason::SoftwareBreakpoint(_) => {}
+        StopReason::Error(e) => {
+            return Err(format!("{} failed with error {:?}", name, e));
+        }
+        _ => return Err(format!("{} never reached LD B,B", name)),
+    }
+
+    let registers = gameboy.registers();
+    let values = [
+        &registers.b,
+        &registers.c,
+        &registers.d,
+        &registers.e,
+        &registers.h,
+        &registers.l,
+    ]
+    .map(|register| *register.get_value());
+
+    if values == FIBONACCI {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} stopped with B C D E H L = {:?}, expected {:?}",
+            name, values, FIBONACCI
+        ))
+    }
+}
+
+/// Runs for `frames` frames and compares the framebuffer against `reference`. On
+/// a mismatch the frame is saved to `target/screenshots` to compare by eye.
+pub fn run_screenshot_test(
+    fixture_name: &str,
+    frames: u64,
+    reference: Reference,
+) -> Result<(), String> {
+    let mut gameboy = load(&read_fixture(fixture_name)?)?;
+
+    while gameboy.frames() < frames {
+        if let StopReason::Error(e) = gameboy.run_frame().stop {
+            return Err(format!("{} failed with error {:?}", fixture_name, e));
+        }
+    }
+
+    let rgb = Palette::GRAYSCALE.to_rgb(gameboy.framebuffer());
+
+    let matches = match reference {
+        Reference::Hash(hash) => fnv1a(gameboy.framebuffer()) == hash,
+        Reference::Image(image) => {
+            read_png(&format!("./tests/fixtures/screenshots/{}", image))? == rgb
+        }
+    };
+
+    if matches {
+        return Ok(());
+    }
+
+    let actual = Path::new("./target/screenshots").join(format!("{}.png", fixture_name));
+    fs::create_dir_all("./target/screenshots").map_err(|e| e.to_string())?;
+    save_png(&actual, SCREEN_WIDTH, SCREEN_HEIGHT, &rgb).map_err(|e| e.to_string())?;
+
+    Err(format!(
+        "{} doesn't match its reference after {} frames. Hash {:#X}, saved to {}",
+        fixture_name,
+        frames,
+        fnv1a(gameboy.framebuffer()),
+        actual.display()
+    ))
+}
+
+fn read_png(location: &str) -> Result<Vec<u8>, String> {
+    let file = File::open(location)
+        .map_err(|_| format!("Failed to read reference image from {}", location))?;
+    let mut reader = png::Decoder::new(file)
+        .read_info()
+        .map_err(|e| format!("Failed to decode {}: {:?}", location, e))?;
+    let mut rgb = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut rgb)
+        .map_err(|e| format!("Failed to decode {}: {:?}", location, e))?;
+    rgb.truncate(info.buffer_size());
+
+    Ok(rgb)
+}
@@ -0,0 +1,80 @@
+use std::hash::Hasher;
+
+const FNV_OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01B3;
+
+/// 64-bit FNV-1a. Unlike `DefaultHasher` it's stable across Rust versions and
+/// platforms, so hashes can be checked in as references.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// `fnv1a` as a `Hasher`, for hashing state with `#[derive(Hash)]`. Integers are
+/// written little endian and sizes as 64 bits, so the result doesn't depend on
+/// the platform.
+pub struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(FNV_OFFSET_BASIS)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as u64);
+    }
+}
+
+#[cfg(test)]
+mod hash_test {
+    use crate::util::hash::{fnv1a, Fnv1a};
+    use std::hash::{Hash, Hasher};
+
+    #[test]
+    fn matches_reference_values() {
+        assert_eq!(fnv1a(b""), 0xCBF2_9CE4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xAF63_DC4C_8601_EC8C);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_F739_67E8);
+    }
+
+    #[test]
+    fn hasher_writes_integers_little_endian() {
+        let mut hasher = Fnv1a::default();
+        0x0201u16.hash(&mut hasher);
+        2usize.hash(&mut hasher);
+
+        assert_eq!(
+            hasher.finish(),
+            fnv1a(&[0x01, 0x02, 0x02, 0, 0, 0, 0, 0, 0, 0])
+        );
+    }
+}
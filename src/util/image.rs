@@ -0,0 +1,149 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// The colors DMG shades are shown in, from lightest to darkest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette(pub [[u8; 3]; 4]);
+
+impl Palette {
+    pub const GRAYSCALE: Palette = Palette([
+        [0xFF, 0xFF, 0xFF],
+        [0xAA, 0xAA, 0xAA],
+        [0x55, 0x55, 0x55],
+        [0x00, 0x00, 0x00],
+    ]);
+
+    /// The green tint of the original DMG screen.
+    pub const CLASSIC_GREEN: Palette = Palette([
+        [0x9B, 0xBC, 0x0F],
+        [0x8B, 0xAC, 0x0F],
+        [0x30, 0x62, 0x30],
+        [0x0F, 0x38, 0x0F],
+    ]);
+
+    /// 8-bit RGB pixels for a buffer of shades from 0 to 3.
+    pub fn to_rgb(&self, shades: &[u8]) -> Vec<u8> {
+        shades
+            .iter()
+            .flat_map(|&shade| self.0[(shade & 0b11) as usize])
+            .collect()
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::GRAYSCALE
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    /// Either `grayscale`, `green`, or four comma separated RRGGBB colors from
+    /// lightest to darkest.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "grayscale" | "gray" => return Ok(Palette::GRAYSCALE),
+            "green" | "classic" => return Ok(Palette::CLASSIC_GREEN),
+            _ => {}
+        }
+
+        let colors = s
+            .split(',')
+            .map(|color| {
+                let color = color.trim().trim_start_matches('#');
+                let rgb = u32::from_str_radix(color, 16)
+                    .ok()
+                    .filter(|_| color.len() == 6)
+                    .ok_or(format!("Invalid color {}", color))?;
+
+                Ok([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8])
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        match colors[..] {
+            [lightest, light, dark, darkest] => Ok(Palette([lightest, light, dark, darkest])),
+            _ => Err(format!("Expected 4 colors, got {}", colors.len())),
+        }
+    }
+}
+
+/// Writes 8-bit RGB pixels, row by row, to a PNG file.
+pub fn save_png<P: AsRef<Path>>(
+    path: P,
+    width: usize,
+    height: usize,
+    rgb: &[u8],
+) -> io::Result<()> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(rgb))
+        .map_err(io::Error::other)
+}
+
+/// Encodes 8-bit RGB pixels, row by row, as a binary PPM.
+pub fn encode_ppm(width: usize, height: usize, rgb: &[u8]) -> Vec<u8> {
+    let mut ppm = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    ppm.extend_from_slice(rgb);
+
+    ppm
+}
+
+pub fn save_ppm<P: AsRef<Path>>(
+    path: P,
+    width: usize,
+    height: usize,
+    rgb: &[u8],
+) -> io::Result<()> {
+    BufWriter::new(File::create(path)?).write_all(&encode_ppm(width, height, rgb))
+}
+
+/// Saves as a PPM if the path ends in `.ppm`, otherwise as a PNG.
+pub fn save_image<P: AsRef<Path>>(
+    path: P,
+    width: usize,
+    height: usize,
+    rgb: &[u8],
+) -> io::Result<()> {
+    let path = path.as_ref();
+
+    match path.extension() {
+        Some(extension) if extension.eq_ignore_ascii_case("ppm") => {
+            save_ppm(path, width, height, rgb)
+        }
+        _ => save_png(path, width, height, rgb),
+    }
+}
+
+#[cfg(test)]
+mod image_test {
+    use crate::util::image::{encode_ppm, Palette};
+
+    #[test]
+    fn palettes_parse_from_names_and_colors() {
+        assert_eq!("green".parse(), Ok(Palette::CLASSIC_GREEN));
+        assert_eq!(
+            "ffffff,#aaaaaa,555555,000000".parse(),
+            Ok(Palette::GRAYSCALE)
+        );
+        assert!("ffffff,aaaaaa".parse::<Palette>().is_err());
+        assert!("ffffff,aaaaaa,555555,zzzzzz".parse::<Palette>().is_err());
+    }
+
+    #[test]
+    fn ppm_has_a_header_then_pixels() {
+        let rgb = Palette::GRAYSCALE.to_rgb(&[0, 3]);
+
+        assert_eq!(
+            encode_ppm(2, 1, &rgb),
+            b"P6\n2 1\n255\n\xFF\xFF\xFF\x00\x00\x00"
+        );
+    }
+}
@@ -1 +1,3 @@
 pub mod byte_ops;
+pub mod hash;
+pub mod image;
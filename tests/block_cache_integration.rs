@@ -0,0 +1,73 @@
+use std::fs;
+use wasmboi::link::capture::ByteCapture;
+use wasmboi::spec::gameboy::GameBoy;
+use wasmboi::spec::register::{Registers, TRegister};
+
+const FIXTURES: &str = "./tests/fixtures";
+/// Long enough for every ROM to get well into its tests.
+const T_CYCLES: u64 = 8_000_000;
+
+fn registers(registers: &Registers) -> [u16; 10] {
+    [
+        *registers.a.get_value() as u16,
+        *registers.b.get_value() as u16,
+        *registers.c.get_value() as u16,
+        *registers.d.get_value() as u16,
+        *registers.e.get_value() as u16,
+        *registers.f.get_value() as u16,
+        *registers.h.get_value() as u16,
+        *registers.l.get_value() as u16,
+        *registers.pc.get_value(),
+        *registers.sp.get_value(),
+    ]
+}
+
+fn load(rom: &[u8], block_cache: bool) -> (GameBoy, ByteCapture) {
+    let serial = ByteCapture::default();
+    let mut gameboy = GameBoy::new(rom).unwrap();
+
+    gameboy.set_block_cache(block_cache);
+    gameboy.connect_link_cable(Box::new(serial.clone()));
+
+    (gameboy, serial)
+}
+
+/// Runs each test ROM with and without the block cache, comparing them after
+/// every instruction.
+#[test]
+fn block_cache_runs_in_lockstep_with_tick() {
+    let mut fixtures: Vec<_> = fs::read_dir(FIXTURES)
+        .unwrap()
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "gb"))
+        .collect();
+    fixtures.sort();
+
+    for path in fixtures {
+        let rom = fs::read(&path).unwrap();
+        let (mut gameboy, serial) = load(&rom, false);
+        let (mut cached, cached_serial) = load(&rom, true);
+
+        while gameboy.t_cycles() < T_CYCLES {
+            let cycles = gameboy.cycle().unwrap();
+            let cached_cycles = cached.cycle().unwrap();
+
+            assert_eq!(
+                (cycles, registers(gameboy.registers())),
+                (cached_cycles, registers(cached.registers())),
+                "{} diverged at t-cycle {}",
+                path.display(),
+                gameboy.t_cycles()
+            );
+        }
+
+        assert_eq!(
+            gameboy.state_hash(),
+            cached.state_hash(),
+            "{}",
+            path.display()
+        );
+        assert_eq!(gameboy.framebuffer(), cached.framebuffer());
+        assert_eq!(serial.output(), cached_serial.output());
+    }
+}
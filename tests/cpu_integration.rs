@@ -1,7 +1,6 @@
 use ntest::timeout;
 
-mod util;
-use util::run_integration_test as run_test;
+use wasmboi::test_support::run_integration_test as run_test;
 
 #[test]
 #[timeout(2000)]
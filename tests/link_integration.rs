@@ -0,0 +1,151 @@
+use std::fs;
+use std::io::Read;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use wasmboi::test_support::rom_with_program;
+
+/// Sends 'M' on the internal clock until the slave answers, then sends the
+/// answer back until the slave answers again, and stops on `LD B,B`.
+const MASTER: [u8; 43] = [
+    0x3E, b'M', // LD A, 'M'
+    0xE0, 0x01, // LDH (SB), A
+    0x3E, 0x81, // LD A, 0x81
+    0xE0, 0x02, // LDH (SC), A
+    0xF0, 0x02, // LDH A, (SC)
+    0xCB, 0x7F, // BIT 7, A
+    0x20, 0xFA, // JR NZ, -6
+    0xF0, 0x01, // LDH A, (SB)
+    0xFE, 0xFF, // CP 0xFF
+    0x28, 0xEC, // JR Z, -20
+    0x4F, // LD C, A
+    0x79, // LD A, C
+    0xE0, 0x01, // LDH (SB), A
+    0x3E, 0x81, // LD A, 0x81
+    0xE0, 0x02, // LDH (SC), A
+    0xF0, 0x02, // LDH A, (SC)
+    0xCB, 0x7F, // BIT 7, A
+    0x20, 0xFA, // JR NZ, -6
+    0xF0, 0x01, // LDH A, (SB)
+    0xFE, 0xFF, // CP 0xFF
+    0x28, 0xED, // JR Z, -19
+    0x40, // LD B, B
+    0x18, 0xFE, // JR -2
+];
+
+/// Waits on the external clock with 'S' in SB, then again with whatever came
+/// in, and stops on `LD B,B`.
+const SLAVE: [u8; 27] = [
+    0x3E, b'S', // LD A, 'S'
+    0xE0, 0x01, // LDH (SB), A
+    0x3E, 0x80, // LD A, 0x80
+    0xE0, 0x02, // LDH (SC), A
+    0xF0, 0x02, // LDH A, (SC)
+    0xCB, 0x7F, // BIT 7, A
+    0x20, 0xFA, // JR NZ, -6
+    0x3E, 0x80, // LD A, 0x80
+    0xE0, 0x02, // LDH (SC), A
+    0xF0, 0x02, // LDH A, (SC)
+    0xCB, 0x7F, // BIT 7, A
+    0x20, 0xFA, // JR NZ, -6
+    0x40, // LD B, B
+    0x18, 0xFE, // JR -2
+];
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn write_rom(dir: &Path, name: &str, program: &[u8]) -> PathBuf {
+    let path = dir.join(name);
+    fs::write(&path, rom_with_program(program)).unwrap();
+
+    path
+}
+
+/// Runs `wasmboi` on `rom` as fast as possible, printing the serial port to stdout
+/// and stopping at `LD B,B`.
+fn spawn(rom: &Path, link: (&str, &str)) -> (Child, JoinHandle<String>) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_wasmboi"))
+        .env("ROM", rom)
+        .env(link.0, link.1)
+        .env("TURBO", "true")
+        .env("SOFTWARE_BREAKPOINTS", "true")
+        .env("SERIAL_PORT_STDOUT", "true")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let mut stdout = child.stdout.take().unwrap();
+    let output = thread::spawn(move || {
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).unwrap();
+        output
+    });
+
+    (child, output)
+}
+
+/// Waits for `child` to exit, killing it if it takes too long. Returns whether it
+/// exited successfully.
+fn finish(child: &mut Child, deadline: Instant) -> bool {
+    loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            return status.success();
+        }
+
+        if Instant::now() > deadline {
+            child.kill().unwrap();
+            panic!("wasmboi didn't stop at its breakpoint in time");
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn processes_exchange_bytes_over_localhost() {
+    let dir = std::env::temp_dir().join(format!("wasmboi-link-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let master_rom = write_rom(&dir, "master.gb", &MASTER);
+    let slave_rom = write_rom(&dir, "slave.gb", &SLAVE);
+    let address = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .to_string();
+    let deadline = Instant::now() + TIMEOUT;
+
+    let (mut host, host_output) = spawn(&master_rom, ("LINK_HOST", &address));
+
+    // The host may not be listening yet, in which case joining fails straight away
+    let (mut join, join_output) = loop {
+        let (mut join, join_output) = spawn(&slave_rom, ("LINK_JOIN", &address));
+
+        thread::sleep(Duration::from_millis(100));
+
+        match join.try_wait().unwrap() {
+            Some(status) if !status.success() && Instant::now() < deadline => continue,
+            _ => break (join, join_output),
+        }
+    };
+
+    assert!(finish(&mut join, deadline));
+    assert!(finish(&mut host, deadline));
+
+    let master = host_output.join().unwrap();
+    let slave = join_output.join().unwrap();
+
+    fs::remove_dir_all(&dir).unwrap();
+
+    // The master sends 'M' until the slave is waiting, then the 'S' it got back
+    // until the slave is waiting again
+    let first_answer = master.find('S').unwrap();
+    assert!(first_answer > 0);
+    assert!(master[..first_answer].chars().all(|c| c == 'M'));
+    assert!(master[first_answer..].chars().all(|c| c == 'S'));
+    assert_eq!(slave, "SM");
+}
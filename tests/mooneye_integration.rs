@@ -0,0 +1,135 @@
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use wasmboi::test_support::run_breakpoint_test;
+
+const SUITE: &str = "./tests/fixtures/mooneye";
+const EXPECTED_FAILURES: &str = "./tests/fixtures/mooneye/expected_failures.txt";
+
+/// Mooneye tests finish within a few emulated seconds.
+const MAX_FRAMES: u64 = 60 * 20;
+
+/// Tests are suffixed with the models they pass on, e.g. `boot_regs-dmgABC.gb` or
+/// `di_timing-GS.gb`. Untagged tests pass on every model.
+fn runs_on_dmg(name: &str) -> bool {
+    let stem = name.trim_end_matches(".gb");
+
+    match stem.rsplit_once('-') {
+        Some((_, models)) => {
+            models.starts_with("dmgABC")
+                || (models.contains('G') && models.chars().all(|c| c.is_ascii_uppercase()))
+        }
+        None => true,
+    }
+}
+
+fn find_roms(dir: &Path, roms: &mut Vec<String>) {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .map(|entries| entries.filter_map(Result::ok).map(|e| e.path()).collect())
+        .unwrap_or_default();
+    entries.sort();
+
+    for path in entries {
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+
+        if path.is_dir() {
+            find_roms(&path, roms);
+        } else if name.ends_with(".gb") && runs_on_dmg(&name) {
+            let relative = path.strip_prefix(SUITE).unwrap();
+            roms.push(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+}
+
+fn expected_failures() -> BTreeSet<String> {
+    fs::read_to_string(EXPECTED_FAILURES)
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn mooneye_suite() -> Result<(), String> {
+    let mut roms = vec![];
+    find_roms(Path::new(SUITE), &mut roms);
+
+    if roms.is_empty() {
+        return Err(format!(
+            "No mooneye test roms in {}, see its README.md",
+            SUITE
+        ));
+    }
+
+    let mut failures = BTreeSet::new();
+
+    for rom in &roms {
+        let fixture = format!("mooneye/{}", rom);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            run_breakpoint_test(&fixture, MAX_FRAMES)
+        }));
+
+        match result {
+            Ok(Ok(())) => println!("pass {}", rom),
+            Ok(Err(e)) => {
+                println!("FAIL {}: {}", rom, e);
+                failures.insert(rom.clone());
+            }
+            Err(_) => {
+                println!("FAIL {}: panicked", rom);
+                failures.insert(rom.clone());
+            }
+        }
+    }
+
+    println!(
+        "{} of {} mooneye tests passed",
+        roms.len() - failures.len(),
+        roms.len()
+    );
+
+    if env::var("MOONEYE_BLESS").unwrap_or("false".into()) == "true" {
+        let mut list = fs::read_to_string(EXPECTED_FAILURES)
+            .unwrap_or_default()
+            .lines()
+            .take_while(|line| line.starts_with('#'))
+            .map(|line| format!("{}\n", line))
+            .collect::<String>();
+        list.extend(failures.iter().map(|rom| format!("{}\n", rom)));
+
+        return fs::write(EXPECTED_FAILURES, list).map_err(|e| e.to_string());
+    }
+
+    let expected = expected_failures();
+    let regressions: Vec<_> = failures.difference(&expected).collect();
+    // Only tests that were run can be fixed, the list may cover roms not present
+    let fixed: Vec<_> = expected
+        .iter()
+        .filter(|rom| roms.contains(rom) && !failures.contains(*rom))
+        .collect();
+
+    if regressions.is_empty() && fixed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Newly failing: {:?}. Newly passing, remove from {}: {:?}",
+            regressions, EXPECTED_FAILURES, fixed
+        ))
+    }
+}
+
+#[test]
+fn only_dmg_tests_are_run() {
+    assert!(runs_on_dmg("div_write.gb"));
+    assert!(runs_on_dmg("boot_regs-dmgABC.gb"));
+    assert!(runs_on_dmg("di_timing-GS.gb"));
+    assert!(runs_on_dmg("boot_hwio-dmgABCmgb.gb"));
+    assert!(!runs_on_dmg("boot_regs-dmg0.gb"));
+    assert!(!runs_on_dmg("boot_regs-mgb.gb"));
+    assert!(!runs_on_dmg("boot_regs-sgb2.gb"));
+    assert!(!runs_on_dmg("boot_div-S.gb"));
+}
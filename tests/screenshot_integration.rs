@@ -0,0 +1,59 @@
+use ntest::timeout;
+
+use wasmboi::test_support::{
+    rom_with_program, run_breakpoint_test, run_breakpoint_test_rom, run_screenshot_test, Reference,
+};
+
+/// A ROM that loads `registers` into B, C, D, E, H and L, then hits `LD B,B`.
+fn rom_loading(registers: [u8; 6]) -> Vec<u8> {
+    let mut program = vec![];
+
+    // LD B, n through LD L, n
+    for (opcode, value) in [0x06, 0x0E, 0x16, 0x1E, 0x26, 0x2E].iter().zip(registers) {
+        program.extend_from_slice(&[*opcode, value]);
+    }
+
+    program.extend_from_slice(&[
+        0x40, // LD B, B
+        0x18, 0xFE, // JR -2
+    ]);
+
+    rom_with_program(&program)
+}
+
+#[test]
+#[timeout(2000)]
+fn breakpoint_test_passes_on_fibonacci_registers() -> Result<(), String> {
+    run_breakpoint_test_rom("fibonacci", &rom_loading([3, 5, 8, 13, 21, 34]), 10)
+}
+
+#[test]
+#[timeout(2000)]
+fn breakpoint_test_fails_on_other_registers() {
+    assert!(run_breakpoint_test_rom("failure", &rom_loading([0x42; 6]), 10).is_err());
+}
+
+#[test]
+#[timeout(10000)]
+fn blargg_01_special_screen() -> Result<(), String> {
+    run_screenshot_test("01_special.gb", 150, Reference::Image("01_special.png"))
+}
+
+#[test]
+#[timeout(10000)]
+fn blargg_01_special_screen_hash() -> Result<(), String> {
+    run_screenshot_test("01_special.gb", 150, Reference::Hash(0x206B_F8EB_BA54_B21E))
+}
+
+#[test]
+#[timeout(10000)]
+#[ignore = "dmg-acid2.gb and its reference image aren't checked in yet, see the README"]
+fn dmg_acid2_screen() -> Result<(), String> {
+    run_screenshot_test("dmg-acid2.gb", 60, Reference::Image("dmg-acid2.png"))
+}
+
+#[test]
+#[timeout(10000)]
+fn mooneye_div_write() -> Result<(), String> {
+    run_breakpoint_test("mooneye/acceptance/timer/div_write.gb", 600)
+}
@@ -0,0 +1,252 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use wasmboi::spec::bus::{Access, AccessKind, Bus, FlatBus, RecordingBus};
+use wasmboi::spec::cpu::{CPU, TCPU};
+use wasmboi::spec::register::{Registers, TRegister};
+
+const VECTORS: &str = "./tests/fixtures/sst";
+
+#[derive(Deserialize)]
+struct State {
+    pc: u16,
+    sp: u16,
+    a: u8,
+    b: u8,
+    c: u8,
+    d: u8,
+    e: u8,
+    f: u8,
+    h: u8,
+    l: u8,
+    #[serde(default)]
+    ime: u8,
+    ram: Vec<(u16, u8)>,
+}
+
+#[derive(Deserialize)]
+struct TestCase {
+    name: String,
+    initial: State,
+    #[serde(rename = "final")]
+    expected: State,
+    /// The bus activity of each M-cycle, null when the bus is idle.
+    cycles: Vec<serde_json::Value>,
+}
+
+fn registers(registers: &Registers) -> [(&'static str, u16); 10] {
+    [
+        ("a", *registers.a.get_value() as u16),
+        ("b", *registers.b.get_value() as u16),
+        ("c", *registers.c.get_value() as u16),
+        ("d", *registers.d.get_value() as u16),
+        ("e", *registers.e.get_value() as u16),
+        ("f", *registers.f.get_value() as u16),
+        ("h", *registers.h.get_value() as u16),
+        ("l", *registers.l.get_value() as u16),
+        ("pc", *registers.pc.get_value()),
+        ("sp", *registers.sp.get_value()),
+    ]
+}
+
+fn load(state: &State) -> (CPU, RecordingBus<FlatBus>) {
+    let mut cpu = CPU::new().unwrap();
+    let mut bus = RecordingBus::new(FlatBus::default());
+    let registers = cpu.registers_mut();
+
+    registers.a.set_value(state.a);
+    registers.b.set_value(state.b);
+    registers.c.set_value(state.c);
+    registers.d.set_value(state.d);
+    registers.e.set_value(state.e);
+    registers.f.set_value(state.f);
+    registers.h.set_value(state.h);
+    registers.l.set_value(state.l);
+    registers.pc.set_value(state.pc);
+    registers.sp.set_value(state.sp);
+    cpu.set_ime(state.ime != 0);
+
+    for &(address, value) in &state.ram {
+        bus.write_byte(address, value);
+    }
+
+    bus.accesses.clear();
+
+    (cpu, bus)
+}
+
+/// A cycle as `[address, value, "rwm"]`, where the flags show whether it was a
+/// read or a write. Idle cycles are null.
+fn access(cycle: &serde_json::Value) -> Option<Access> {
+    let cycle = cycle.as_array()?;
+    let flags = cycle.get(2)?.as_str()?;
+
+    Some(Access {
+        address: cycle.first()?.as_u64()? as u16,
+        value: cycle.get(1)?.as_u64()? as u8,
+        kind: if flags.contains('w') {
+            AccessKind::Write
+        } else {
+            AccessKind::Read
+        },
+    })
+}
+
+/// Runs one instruction, returning every field that doesn't match.
+fn run(test: &TestCase) -> Vec<String> {
+    let (mut cpu, mut bus) = load(&test.initial);
+    let mut differences = vec![];
+
+    let cycles = match cpu.tick(&mut bus) {
+        Ok(cycles) => cycles as usize,
+        Err(e) => return vec![format!("error {:?}", e)],
+    };
+
+    let (expected_cpu, _) = load(&test.expected);
+    let actual = registers(cpu.registers());
+
+    for ((name, expected), (_, actual)) in registers(expected_cpu.registers()).iter().zip(actual) {
+        if *expected != actual {
+            differences.push(format!(
+                "{}: expected {:#X}, got {:#X}",
+                name, expected, actual
+            ));
+        }
+    }
+
+    if cpu.ime() != (test.expected.ime != 0) {
+        differences.push(format!(
+            "ime: expected {}, got {}",
+            test.expected.ime,
+            cpu.ime()
+        ));
+    }
+
+    for &(address, expected) in &test.expected.ram {
+        let actual = bus.read_byte(address);
+
+        if expected != actual {
+            differences.push(format!(
+                "ram[{:#06X}]: expected {:#X}, got {:#X}",
+                address, expected, actual
+            ));
+        }
+    }
+
+    let expected_accesses: Vec<_> = test.cycles.iter().filter_map(access).collect();
+
+    if let Some(index) = (0..expected_accesses.len().max(bus.accesses.len()))
+        .find(|&i| expected_accesses.get(i) != bus.accesses.get(i))
+    {
+        differences.push(format!(
+            "access {}: expected {:X?}, got {:X?}",
+            index,
+            expected_accesses.get(index),
+            bus.accesses.get(index)
+        ));
+    }
+
+    if cycles != test.cycles.len() {
+        differences.push(format!(
+            "m-cycles: expected {}, got {}",
+            test.cycles.len(),
+            cycles
+        ));
+    }
+
+    differences
+}
+
+/// Opcodes that don't exist on the SM83, plus the CB prefix which has its own files.
+const NO_VECTORS: [u8; 12] = [
+    0xCB, 0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD,
+];
+
+fn vector_files() -> Result<Vec<PathBuf>, String> {
+    let mut files: Vec<_> = fs::read_dir(VECTORS)
+        .map_err(|e| e.to_string())?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect();
+    files.sort();
+
+    Ok(files)
+}
+
+fn run_files(files: &[PathBuf]) -> Result<(), String> {
+    let mut failures = vec![];
+    let mut total = 0;
+
+    for file in files {
+        let json = fs::read_to_string(file).map_err(|e| e.to_string())?;
+        let tests: Vec<TestCase> = serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse {}: {}", file.display(), e))?;
+        let file_name = Path::new(file).file_name().unwrap().to_string_lossy();
+
+        total += tests.len();
+
+        // Report the first failure in each file, the rest are usually the same bug
+        if let Some((test, differences)) = tests
+            .iter()
+            .map(|test| (test, run(test)))
+            .find(|(_, differences)| !differences.is_empty())
+        {
+            failures.push(format!(
+                "{} \"{}\": {}",
+                file_name,
+                test.name,
+                differences.join(", ")
+            ));
+        }
+    }
+
+    println!("Ran {} single step tests from {} files", total, files.len());
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} of {} files failed:\n{}",
+            failures.len(),
+            files.len(),
+            failures.join("\n")
+        ))
+    }
+}
+
+#[test]
+fn single_step_tests() -> Result<(), String> {
+    run_files(&vector_files()?)
+}
+
+#[test]
+#[ignore = "the upstream v1 vectors aren't checked in yet, see tests/fixtures/sst/README.md"]
+fn every_opcode_has_vectors() -> Result<(), String> {
+    let files = vector_files()?;
+    let names: Vec<_> = files
+        .iter()
+        .map(|file| file.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+
+    let missing: Vec<_> = (0..=0xFFu8)
+        .filter(|opcode| !NO_VECTORS.contains(opcode))
+        .map(|opcode| format!("{:02x}.json", opcode))
+        .chain((0..=0xFFu8).map(|opcode| format!("cb {:02x}.json", opcode)))
+        .filter(|name| !names.contains(name))
+        .collect();
+
+    if !missing.is_empty() {
+        return Err(format!(
+            "{} opcodes have no vectors in {}, e.g. {}",
+            missing.len(),
+            VECTORS,
+            missing[0]
+        ));
+    }
+
+    run_files(&files)
+}